@@ -1,233 +1,1081 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
 use crate::{
-    Position,
+    Move,
     board::BoardTrait,
-    pieces::{Color, Piece, PieceType},
+    book::{Book, BookOptions},
+    eval::{self, Weights},
+    ordering::{MoveOrdering, capture_value},
+    pawns::PawnTable,
+    pieces::{Color, PieceType, Promotion},
+    tt::{Bound, Entry, TranspositionTable},
+    zobrist,
 };
 
-// 1. loop through all pieces on the board
-// 2. for each piece, generate all possible moves
-// 3. for each move, evaluate the board
-// 4. return the best move
-pub fn generate_move(color: Color, board: &dyn BoardTrait) -> Option<(&PieceType, Position)> {
-    let pieces = match color {
-        Color::Black => board.get_all_black_pieces(),
-        Color::White => board.get_all_white_pieces(),
-    };
+pub type Score = i32;
 
-    let mut best_score = 0;
-    let mut best_move = Option::None;
-    for piece in pieces {
-        let possible_moves = piece.possible_moves(board);
-        for new_position in possible_moves {
-            let mut cloned_board = board.clone_as_a();
-            let future_board = cloned_board.as_mut();
-
-            let mut future_piece = piece.clone();
-            if let Ok(_) = future_piece.move_to(new_position, future_board) {
-                let score = future_board.evaluate(&color);
-                if score > best_score {
-                    best_score = score;
-                    best_move = Option::Some((piece, new_position));
-                }
+/// Score of being checkmated right now. Mates further away score closer to zero by one point
+/// per ply, so the search prefers the quickest mate and the slowest loss.
+pub const MATE: Score = 30_000;
+pub const MAX_DEPTH: u8 = 64;
+/// Deepest ply reachable once check extensions and the quiescence search are added.
+const MAX_PLY: u8 = MAX_DEPTH * 2;
+const INFINITY: Score = MATE + 1;
+/// Margin added to a capture's gain before delta pruning drops it as unable to raise alpha.
+const DELTA_MARGIN: Score = 200;
+/// Futility pruning applies up to this depth, with a margin per ply of depth left.
+const FUTILITY_DEPTH: u8 = 3;
+const FUTILITY_MARGIN: Score = 150;
+const NULL_MOVE_DEPTH: u8 = 3;
+/// Late move reductions start at this depth, after this many moves have been searched.
+const LMR_DEPTH: u8 = 3;
+const LMR_MOVES: usize = 3;
+/// Aspiration windows start at this depth with this half width, and fall back to a full
+/// window once they would grow past the maximum.
+const ASPIRATION_DEPTH: u8 = 4;
+const ASPIRATION_WINDOW: Score = 50;
+const MAX_ASPIRATION_WINDOW: Score = 800;
+/// Depth used by `generate_move`, which has no time limit.
+pub const DEFAULT_DEPTH: u8 = 3;
+
+/// Limits for a search. A search without limits runs until it is stopped.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub time: Option<Duration>,
+}
+
+/// Selective search techniques, all enabled by default. Each one can be switched off by name
+/// through the engine options to measure what it is worth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchOptions {
+    pub null_move: bool,
+    pub late_move_reductions: bool,
+    pub futility: bool,
+    pub check_extensions: bool,
+    pub pvs: bool,
+    pub aspiration_windows: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            null_move: true,
+            late_move_reductions: true,
+            futility: true,
+            check_extensions: true,
+            pvs: true,
+            aspiration_windows: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub const NAMES: [&str; 6] = [
+        "NullMove",
+        "LateMoveReductions",
+        "Futility",
+        "CheckExtensions",
+        "PVS",
+        "AspirationWindows",
+    ];
+
+    fn flag_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name.to_ascii_lowercase().as_str() {
+            "nullmove" => Some(&mut self.null_move),
+            "latemovereductions" => Some(&mut self.late_move_reductions),
+            "futility" => Some(&mut self.futility),
+            "checkextensions" => Some(&mut self.check_extensions),
+            "pvs" => Some(&mut self.pvs),
+            "aspirationwindows" => Some(&mut self.aspiration_windows),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the option called `name` is enabled, or `None` for an unknown name.
+    pub fn get(&self, name: &str) -> Option<bool> {
+        let mut options = *self;
+        options.flag_mut(name).map(|flag| *flag)
+    }
+
+    /// Switches the option called `name`, returning false for an unknown name.
+    pub fn set(&mut self, name: &str, enabled: bool) -> bool {
+        match self.flag_mut(name) {
+            Some(flag) => {
+                *flag = enabled;
+                true
             }
+            None => false,
         }
     }
 
-    best_move
+    /// Every option switched off, leaving a plain alpha-beta search.
+    pub fn none() -> Self {
+        SearchOptions {
+            null_move: false,
+            late_move_reductions: false,
+            futility: false,
+            check_extensions: false,
+            pvs: false,
+            aspiration_windows: false,
+        }
+    }
 }
 
-#[cfg(test)]
-mod test {
+/// Result of a search, also reported after every completed iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u8,
+    /// Score in centipawns from the point of view of the side to move.
+    pub score: Score,
+    pub nodes: u64,
+    pub time: Duration,
+    pub pv: Vec<Move>,
+}
 
-    use std::ops::Range;
+impl SearchInfo {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
 
-    use crate::{
-        Position, Square,
-        ai::generate_move,
-        board::{self, BoardTrait},
-        pieces::{self, Color, PieceType},
+    /// Returns the number of moves to mate, negative when the side to move is getting mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_in(self.score)
+    }
+}
+
+pub fn is_mate_score(score: Score) -> bool {
+    score.abs() > MATE - MAX_DEPTH as Score * 2
+}
+
+pub fn mate_in(score: Score) -> Option<i32> {
+    if !is_mate_score(score) {
+        return None;
+    }
+    let plies = MATE - score.abs();
+    let moves = (plies + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+pub fn generate_move(color: Color, board: &dyn BoardTrait) -> Option<Move> {
+    let stop = AtomicBool::new(false);
+    let limits = SearchLimits {
+        depth: Some(DEFAULT_DEPTH),
+        time: None,
     };
+    search(board, color, &limits, &stop, |_| {}).best_move()
+}
 
-    #[test]
-    fn test_generate_move() {
-        let mut board = board::new_board();
-        let best_move = generate_move(Color::White, &mut board);
-        assert_eq!(best_move.is_some(), true);
-    }
-
-    #[derive(Debug, Clone)]
-    struct MockBoard {
-        pub white: Vec<Square>,
-        pub black: Vec<Square>,
-        pub is_king_check: bool,
-    }
-
-    impl MockBoard {
-        pub fn new() -> Self {
-            let white = MockBoard::fill_white();
-            let black = MockBoard::fill_black();
-            MockBoard {
-                white,
-                black,
-                is_king_check: false,
-            }
-        }
-        fn fill_white() -> Vec<Square> {
-            let mut squares = Vec::new();
-            for y in 1..3 {
-                let range: Range<u8> = 97..105;
-                for x in range {
-                    squares.push(Square {
-                        piece: None,
-                        x: x as char,
-                        y,
-                    });
-                }
+/// Searches `board` for the best move of `color` with a fresh engine, see `Engine::search`.
+/// The engine is thrown away afterwards, so it only gets a small transposition table.
+pub fn search(
+    board: &dyn BoardTrait,
+    color: Color,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    on_info: impl FnMut(&SearchInfo),
+) -> SearchInfo {
+    Engine::new(1).search(board, color, &[], limits, stop, on_info)
+}
+
+/// Search state kept from one move of a game to the next.
+#[derive(Debug, Clone, Default)]
+pub struct Engine {
+    pub options: SearchOptions,
+    pub book_options: BookOptions,
+    tt: TranspositionTable,
+    ordering: MoveOrdering,
+    pawns: PawnTable,
+    book: Option<Arc<Book>>,
+}
+
+impl Engine {
+    pub fn new(hash_mb: usize) -> Self {
+        Engine {
+            options: SearchOptions::default(),
+            book_options: BookOptions::default(),
+            tt: TranspositionTable::new(hash_mb),
+            ordering: MoveOrdering::default(),
+            pawns: PawnTable::default(),
+            book: None,
+        }
+    }
+
+    /// Replaces the opening book, `None` plays without one.
+    pub fn set_book(&mut self, book: Option<Book>) {
+        self.book = book.map(Arc::new);
+    }
+
+    /// Returns a move of the opening book for `color`, while the game is still within the
+    /// book depth. `history` holds the position keys of the game so far, see `search`.
+    pub fn book_move(&self, board: &dyn BoardTrait, color: Color, history: &[u64]) -> Option<Move> {
+        let book = self.book.as_ref()?;
+        let played = history.len().saturating_sub(1) / 2;
+        if played >= self.book_options.depth {
+            return None;
+        }
+        book.choose(board, color, self.book_options.selection)
+    }
+
+    /// Replaces the transposition table with an empty one of `megabytes`.
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.tt = TranspositionTable::new(megabytes);
+    }
+
+    /// Forgets everything learned, for a new game.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.ordering.clear();
+        self.pawns.clear();
+    }
+
+    pub fn hashfull(&self) -> u16 {
+        self.tt.hashfull()
+    }
+
+    fn searcher<'a>(
+        &'a mut self,
+        stop: &'a AtomicBool,
+        deadline: Option<Instant>,
+        history: &[u64],
+    ) -> Searcher<'a> {
+        Searcher {
+            stop,
+            deadline,
+            nodes: 0,
+            stopped: false,
+            options: self.options,
+            tt: &mut self.tt,
+            ordering: &mut self.ordering,
+            pawns: &mut self.pawns,
+            weights: eval::weights(),
+            positions: history.to_vec(),
+        }
+    }
+
+    /// Resolves the captures of `board` with a quiescence search. Returns the score for
+    /// `color` and the captures expected to be played, which lead to the quiet position whose
+    /// static evaluation the score is under `weights`. Pawn structures are cached across calls,
+    /// so an engine should always be given the same weights.
+    pub fn quiescence(
+        &mut self,
+        board: &dyn BoardTrait,
+        color: Color,
+        weights: &Weights,
+    ) -> (Score, Vec<Move>) {
+        let stop = AtomicBool::new(false);
+        let mut searcher = self.searcher(&stop, None, &[]);
+        searcher.weights = weights;
+        let mut pv = Vec::new();
+        let score = searcher.quiescence(board, color, 0, -INFINITY, INFINITY, &mut pv);
+        (score, pv)
+    }
+
+    /// Searches `board` for the best move of `color` with an iteratively deepened negamax
+    /// alpha-beta search, calling `on_info` after every completed depth. `history` holds the
+    /// position keys of the game so far, any position repeating one of them scores as a draw.
+    /// A move from the opening book is played without searching, as a result of depth zero.
+    pub fn search(
+        &mut self,
+        board: &dyn BoardTrait,
+        color: Color,
+        history: &[u64],
+        limits: &SearchLimits,
+        stop: &AtomicBool,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> SearchInfo {
+        let start = Instant::now();
+        if let Some(m) = self.book_move(board, color, history) {
+            return SearchInfo {
+                depth: 0,
+                score: 0,
+                nodes: 0,
+                time: start.elapsed(),
+                pv: vec![m],
+            };
+        }
+        self.ordering.new_search();
+        let mut searcher = self.searcher(stop, limits.time.map(|time| start + time), history);
+
+        let mut result = SearchInfo {
+            depth: 0,
+            score: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: Vec::new(),
+        };
+        let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = searcher.aspiration(board, color, depth, &result, &mut pv);
+            // An interrupted iteration is only trusted when nothing better is known.
+            if searcher.stopped && !result.pv.is_empty() {
+                break;
             }
-            squares[0].piece = Some(pieces::PieceType::Rook(
-                pieces::Color::White,
-                Position::new('a', 1),
-            ));
-            squares[1].piece = Some(pieces::PieceType::Knight(
-                pieces::Color::White,
-                Position::new('b', 1),
-            ));
-            squares[2].piece = Some(pieces::PieceType::Bishop(
-                pieces::Color::White,
-                Position::new('c', 1),
-            ));
-            squares[3].piece = Some(pieces::PieceType::Queen(
-                pieces::Color::White,
-                Position::new('d', 1),
-            ));
-            squares[4].piece = Some(pieces::PieceType::King(
-                pieces::Color::White,
-                Position::new('e', 1),
-            ));
-            squares[5].piece = Some(pieces::PieceType::Bishop(
-                pieces::Color::White,
-                Position::new('f', 1),
-            ));
-            squares[6].piece = Some(pieces::PieceType::Knight(
-                pieces::Color::White,
-                Position::new('g', 1),
-            ));
-            squares[7].piece = Some(pieces::PieceType::Rook(
-                pieces::Color::White,
-                Position::new('h', 1),
-            ));
-            for i in 8..16 {
-                squares[i].piece = Some(pieces::PieceType::Pawn(
-                    pieces::Color::White,
-                    Position::new((i as i8 - 8 + 97) as u8 as char, 2),
-                    true,
-                ));
+
+            result = SearchInfo {
+                depth,
+                score,
+                nodes: searcher.nodes,
+                time: start.elapsed(),
+                pv,
+            };
+            if searcher.stopped {
+                break;
+            }
+            on_info(&result);
+
+            if pv_is_forced(&result) {
+                break;
+            }
+        }
+
+        result.nodes = searcher.nodes;
+        result.time = start.elapsed();
+        result
+    }
+}
+
+/// A search that has found a mate within its depth can not find anything better.
+fn pv_is_forced(result: &SearchInfo) -> bool {
+    is_mate_score(result.score) && ((MATE - result.score.abs()) as u8) <= result.depth
+}
+
+struct Searcher<'a> {
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    nodes: u64,
+    stopped: bool,
+    options: SearchOptions,
+    tt: &'a mut TranspositionTable,
+    ordering: &'a mut MoveOrdering,
+    pawns: &'a mut PawnTable,
+    weights: &'a Weights,
+    /// Keys of the game's positions followed by those on the path to the current node.
+    positions: Vec<u64>,
+}
+
+/// Returns whether `color` has anything besides pawns and the king.
+fn has_pieces(board: &dyn BoardTrait, color: Color) -> bool {
+    let pieces = match color {
+        Color::White => board.get_all_white_pieces(),
+        Color::Black => board.get_all_black_pieces(),
+    };
+    pieces
+        .iter()
+        .any(|piece| !matches!(piece, PieceType::Pawn(_, _, _) | PieceType::King(_, _, _)))
+}
+
+/// Mate scores are stored relative to the node rather than the root, so they stay right when
+/// the position is reached again at another ply.
+fn score_to_tt(score: Score, ply: u8) -> Score {
+    if is_mate_score(score) {
+        score + score.signum() * ply as Score
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: Score, ply: u8) -> Score {
+    if is_mate_score(score) {
+        score - score.signum() * ply as Score
+    } else {
+        score
+    }
+}
+
+impl Searcher<'_> {
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped
+            && (self.stop.load(Ordering::Relaxed)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline))
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    fn evaluate(&mut self, board: &dyn BoardTrait, color: Color) -> Score {
+        self.weights.evaluate_cached(board, color, self.pawns)
+    }
+
+    /// Searches the root within an aspiration window around the score of the last iteration,
+    /// widening it until the score falls inside.
+    fn aspiration(
+        &mut self,
+        board: &dyn BoardTrait,
+        color: Color,
+        depth: u8,
+        previous: &SearchInfo,
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        if !self.options.aspiration_windows
+            || depth < ASPIRATION_DEPTH
+            || is_mate_score(previous.score)
+        {
+            return self.negamax(
+                board,
+                color,
+                depth,
+                0,
+                -INFINITY,
+                INFINITY,
+                &previous.pv,
+                pv,
+                true,
+            );
+        }
+
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = previous.score - delta;
+        let mut beta = previous.score + delta;
+        loop {
+            pv.clear();
+            let score = self.negamax(board, color, depth, 0, alpha, beta, &previous.pv, pv, true);
+            if self.stopped || (alpha < score && score < beta) {
+                return score;
+            }
+
+            delta *= 2;
+            if delta > MAX_ASPIRATION_WINDOW {
+                alpha = -INFINITY;
+                beta = INFINITY;
+            } else if score <= alpha {
+                alpha = (score - delta).max(-INFINITY);
+            } else {
+                beta = (score + delta).min(INFINITY);
+            }
+        }
+    }
+
+    /// Returns the score of `board` for `color`, writing the best line found into `pv`.
+    /// `previous_pv` is the line of the last iteration and is searched first. `allow_null` is
+    /// false right after a null move, so two of them never follow each other.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        board: &dyn BoardTrait,
+        color: Color,
+        mut depth: u8,
+        ply: u8,
+        mut alpha: Score,
+        beta: Score,
+        previous_pv: &[Move],
+        pv: &mut Vec<Move>,
+        allow_null: bool,
+    ) -> Score {
+        self.nodes += 1;
+        if ply > 0 && self.should_stop() {
+            return 0;
+        }
+
+        let key = board.zobrist_key() ^ zobrist::side(color);
+        if ply > 0 && self.positions.contains(&key) {
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return self.evaluate(board, color);
+        }
+        let pv_node = beta - alpha > 1;
+
+        let mut hash_move = None;
+        if let Some(entry) = self.tt.probe(key) {
+            hash_move = entry.best_move;
+            let score = score_from_tt(entry.score, ply);
+            // Exact scores inside the window are searched again to get a principal variation.
+            let cutoff = match entry.bound {
+                Bound::Exact => score <= alpha || score >= beta,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if ply > 0 && entry.depth >= depth && cutoff {
+                return score;
             }
+        }
 
-            squares
+        let in_check = board.is_king_check(&color);
+        if in_check && self.options.check_extensions {
+            depth += 1;
+        }
+        if depth == 0 {
+            return self.quiescence(board, color, ply, alpha, beta, pv);
+        }
+
+        let mut moves = board.legal_moves(&color);
+        if moves.is_empty() {
+            let score = if in_check { -MATE + ply as Score } else { 0 };
+            self.store(key, MAX_DEPTH, Bound::Exact, score, ply, None);
+            return score;
         }
 
-        fn fill_black() -> Vec<Square> {
-            let mut squares = Vec::new();
-            for y in 7..9 {
-                let range: Range<u8> = 97..105;
-                for x in range {
-                    squares.push(Square {
-                        piece: None,
-                        x: x as char,
-                        y,
-                    });
+        let static_eval = self.evaluate(board, color);
+        let mut futile = false;
+        if !pv_node && !in_check {
+            // Reverse futility: a quiet position far above beta will not drop below it.
+            if self.options.futility
+                && depth <= FUTILITY_DEPTH
+                && !is_mate_score(beta)
+                && static_eval - FUTILITY_MARGIN * depth as Score >= beta
+            {
+                return static_eval;
+            }
+
+            // Null move: if passing still beats beta, a real move will too. Positions with only
+            // pawns left are skipped, as zugzwang makes passing better than any move there.
+            if self.options.null_move
+                && allow_null
+                && depth >= NULL_MOVE_DEPTH
+                && static_eval >= beta
+                && has_pieces(board, color)
+            {
+                let reduction = if depth >= 6 { 3 } else { 2 };
+                let mut null_board = board.clone_as_a();
+                null_board.set_en_passant(None);
+                self.positions.push(key);
+                let score = -self.negamax(
+                    null_board.as_ref(),
+                    color.opponent(),
+                    (depth - 1).saturating_sub(reduction),
+                    ply + 1,
+                    -beta,
+                    -beta + 1,
+                    &[],
+                    &mut Vec::new(),
+                    false,
+                );
+                self.positions.pop();
+                if self.stopped {
+                    return 0;
+                }
+                if score >= beta {
+                    return if is_mate_score(score) { beta } else { score };
                 }
             }
-            squares[8].piece = Some(pieces::PieceType::Rook(
-                pieces::Color::Black,
-                Position::new('a', 8),
-            ));
-            squares[9].piece = Some(pieces::PieceType::Knight(
-                pieces::Color::Black,
-                Position::new('b', 8),
-            ));
-            squares[10].piece = Some(pieces::PieceType::Bishop(
-                pieces::Color::Black,
-                Position::new('c', 8),
-            ));
-            squares[11].piece = Some(pieces::PieceType::Queen(
-                pieces::Color::Black,
-                Position::new('d', 8),
-            ));
-            squares[12].piece = Some(pieces::PieceType::King(
-                pieces::Color::Black,
-                Position::new('e', 8),
-            ));
-            squares[13].piece = Some(pieces::PieceType::Bishop(
-                pieces::Color::Black,
-                Position::new('f', 8),
-            ));
-            squares[14].piece = Some(pieces::PieceType::Knight(
-                pieces::Color::Black,
-                Position::new('g', 8),
-            ));
-            squares[15].piece = Some(pieces::PieceType::Rook(
-                pieces::Color::Black,
-                Position::new('h', 8),
-            ));
-            for i in 0..8 {
-                squares[i].piece = Some(pieces::PieceType::Pawn(
-                    pieces::Color::Black,
-                    Position::new((i as i8 + 97) as u8 as char, 7),
+
+            // Futility: near the horizon quiet moves can not lift a hopeless position to alpha.
+            futile = self.options.futility
+                && depth <= FUTILITY_DEPTH
+                && !is_mate_score(alpha)
+                && static_eval + FUTILITY_MARGIN * depth as Score <= alpha;
+        }
+
+        let first = previous_pv.first().copied().or(hash_move);
+        self.ordering.order(board, color, &mut moves, first, ply);
+
+        let original_alpha = alpha;
+        self.positions.push(key);
+
+        let mut best_score = -MATE - 1;
+        let mut line = Vec::new();
+        for (index, m) in moves.iter().enumerate() {
+            let mut next_board = board.clone_as_a();
+            if next_board.make_move(m).is_err() {
+                continue;
+            }
+            let reducible = self.options.late_move_reductions
+                && !in_check
+                && depth >= LMR_DEPTH
+                && index >= LMR_MOVES;
+            let quiet = index > 0
+                && (futile || reducible)
+                && capture_value(board, m).is_none()
+                && m.promotion.is_none()
+                && !next_board.is_king_check(&color.opponent());
+            if futile && quiet {
+                continue;
+            }
+
+            let next_pv = match previous_pv.split_first() {
+                Some((first, rest)) if index == 0 && first == m => rest,
+                _ => &[],
+            };
+            line.clear();
+            let score = if index == 0 {
+                -self.negamax(
+                    next_board.as_ref(),
+                    color.opponent(),
+                    depth - 1,
+                    ply + 1,
+                    -beta,
+                    -alpha,
+                    next_pv,
+                    &mut line,
+                    true,
+                )
+            } else {
+                // Principal variation nodes are reduced one ply less than the rest.
+                let reduction = if reducible && quiet {
+                    let reduction = if depth >= 6 && index >= LMR_MOVES * 2 {
+                        2
+                    } else {
+                        1
+                    };
+                    reduction - pv_node as u8
+                } else {
+                    0
+                };
+                // Principal variation search: later moves only have to prove they are no better
+                // than alpha, which a null window does cheaply.
+                let window = if self.options.pvs { alpha + 1 } else { beta };
+
+                let mut score = -self.negamax(
+                    next_board.as_ref(),
+                    color.opponent(),
+                    depth - 1 - reduction,
+                    ply + 1,
+                    -window,
+                    -alpha,
+                    &[],
+                    &mut line,
                     true,
-                ));
+                );
+                if reduction > 0 && score > alpha && !self.stopped {
+                    line.clear();
+                    score = -self.negamax(
+                        next_board.as_ref(),
+                        color.opponent(),
+                        depth - 1,
+                        ply + 1,
+                        -window,
+                        -alpha,
+                        &[],
+                        &mut line,
+                        true,
+                    );
+                }
+                if window < beta && score > alpha && score < beta && !self.stopped {
+                    line.clear();
+                    score = -self.negamax(
+                        next_board.as_ref(),
+                        color.opponent(),
+                        depth - 1,
+                        ply + 1,
+                        -beta,
+                        -alpha,
+                        &[],
+                        &mut line,
+                        true,
+                    );
+                }
+                score
+            };
+            if self.stopped {
+                if best_score == -MATE - 1 {
+                    pv.clear();
+                    pv.push(*m);
+                }
+                break;
             }
 
-            squares
+            if score > best_score {
+                best_score = score;
+                pv.clear();
+                pv.push(*m);
+                pv.extend_from_slice(&line);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                self.ordering.record_cutoff(board, color, m, depth, ply);
+                break;
+            }
         }
-    }
+        self.positions.pop();
 
-    impl BoardTrait for MockBoard {
-        fn move_piece(
-            &mut self,
-            from: Position,
-            to: Position,
-        ) -> Result<Option<PieceType>, crate::pieces::ChessError> {
-            todo!()
+        if !self.stopped {
+            let bound = if best_score >= beta {
+                Bound::Lower
+            } else if best_score > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.store(key, depth, bound, best_score, ply, pv.first().copied());
         }
+        best_score
+    }
 
-        fn get_piece(&self, position: Position) -> Option<&PieceType> {
-            todo!()
+    /// Resolves captures and queen promotions until the position is quiet, so the evaluation
+    /// never sees a piece that is about to be recaptured. The side to move may stand pat on
+    /// the static evaluation unless it is in check, where every evasion is searched.
+    fn quiescence(
+        &mut self,
+        board: &dyn BoardTrait,
+        color: Color,
+        ply: u8,
+        mut alpha: Score,
+        beta: Score,
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
         }
 
-        fn get_all_white_pieces(&self) -> Vec<&PieceType> {
-            self.white
-                .iter()
-                .filter_map(|square| square.piece.as_ref())
-                .collect()
+        let in_check = board.is_king_check(&color);
+        let mut moves = board.legal_moves(&color);
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as Score } else { 0 };
         }
 
-        fn get_all_black_pieces(&self) -> Vec<&PieceType> {
-            self.black
-                .iter()
-                .filter_map(|square| square.piece.as_ref())
-                .collect()
+        let stand_pat = self.evaluate(board, color);
+        if ply >= MAX_PLY {
+            return stand_pat;
         }
-
-        fn is_king_check(&self, color: &Color) -> bool {
-            self.is_king_check
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
         }
 
-        fn can_king_move_safe_position(&self, color: &Color) -> bool {
-            todo!()
+        let mut best_score = if in_check {
+            -MATE + ply as Score
+        } else {
+            stand_pat
+        };
+        self.ordering.order(board, color, &mut moves, None, ply);
+        for m in moves.iter() {
+            if !in_check {
+                let gain = match (capture_value(board, m), m.promotion) {
+                    (_, Some(Promotion::Queen)) => None,
+                    (Some(gain), _) => Some(gain),
+                    (None, _) => continue,
+                };
+                // Delta pruning: even winning the piece for free would not reach alpha.
+                if let Some(gain) = gain
+                    && stand_pat + gain + DELTA_MARGIN <= alpha
+                {
+                    continue;
+                }
+            }
+
+            let mut next_board = board.clone_as_a();
+            if next_board.make_move(m).is_err() {
+                continue;
+            }
+            let mut line = Vec::new();
+            let score = -self.quiescence(
+                next_board.as_ref(),
+                color.opponent(),
+                ply + 1,
+                -beta,
+                -alpha,
+                &mut line,
+            );
+            if self.stopped {
+                break;
+            }
+
+            if score > best_score {
+                best_score = score;
+                pv.clear();
+                pv.push(*m);
+                pv.extend_from_slice(&line);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
         }
 
-        fn evaluate(&self, color: &Color) -> i16 {
-            todo!()
+        best_score
+    }
+
+    fn store(
+        &mut self,
+        key: u64,
+        depth: u8,
+        bound: Bound,
+        score: Score,
+        ply: u8,
+        best_move: Option<Move>,
+    ) {
+        self.tt.store(Entry {
+            key,
+            depth,
+            bound,
+            score: score_to_tt(score, ply),
+            best_move,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        Position,
+        board::{self, GameStatus},
+        book,
+        fen::Fen,
+    };
+
+    fn fen_board(fen: &str) -> Box<dyn BoardTrait> {
+        fen.parse::<Fen>().unwrap().board
+    }
+
+    #[test]
+    fn test_generate_move() {
+        let board = board::new_board();
+        let best_move = generate_move(Color::White, &board);
+        assert!(best_move.is_some());
+    }
+
+    fn search_fen(fen: &str, depth: u8) -> SearchInfo {
+        let fen: Fen = fen.parse().unwrap();
+        let limits = SearchLimits {
+            depth: Some(depth),
+            time: None,
+        };
+        search(
+            fen.board.as_ref(),
+            fen.turn,
+            &limits,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    }
+
+    #[test]
+    fn test_search_finds_mate_in_one() {
+        let info = search_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!(
+            info.best_move(),
+            Some(Move::new(Position::new('a', 1), Position::new('a', 8)))
+        );
+        assert_eq!(info.score, MATE - 1);
+        assert_eq!(info.mate_in(), Some(1));
+        assert_eq!(info.depth, 1, "A mate in one ends the iterative deepening");
+    }
+
+    #[test]
+    fn test_search_finds_mate_in_two() {
+        let info = search_fen("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", 3);
+        assert_eq!(info.mate_in(), Some(2));
+        assert_eq!(info.pv.len(), 3, "PV should hold the whole mating line");
+
+        let mut board = fen_board("k7/8/2K5/8/8/8/8/1R6 w - - 0 1");
+        let mut color = Color::White;
+        for m in info.pv.iter() {
+            board.make_move(m).unwrap();
+            color = color.opponent();
         }
+        assert_eq!(board.status(&color), GameStatus::Checkmate);
+    }
+
+    #[test]
+    fn test_search_sees_mated_side() {
+        let info = search_fen("6k1/5ppp/8/8/8/8/8/R5K1 b - - 0 1", 2);
+        assert!(info.score > -MATE);
+        let info = search_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 2);
+        assert_eq!(info.score, -MATE);
+        assert_eq!(info.best_move(), None);
+    }
+
+    #[test]
+    fn test_search_wins_material() {
+        let info = search_fen("4k3/8/8/3q4/8/8/3R4/3RK3 w - - 0 1", 2);
+        assert!(info.score > 400, "The queen is won, by Rxd5 or after Re2+");
+        let info = search_fen("4k3/8/4p3/3n4/8/8/3Q4/4K3 w - - 0 1", 2);
+        assert_ne!(
+            info.best_move(),
+            Some(Move::new(Position::new('d', 2), Position::new('d', 5))),
+            "Capturing the defended knight loses the queen"
+        );
+    }
+
+    #[test]
+    fn test_quiescence_resolves_captures() {
+        let queen_takes_d5 = Some(Move::new(Position::new('d', 1), Position::new('d', 5)));
+
+        let info = search_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", 1);
+        assert_ne!(
+            info.best_move(),
+            queen_takes_d5,
+            "The pawn is defended by e6"
+        );
+        let defended = info.score;
+
+        let info = search_fen("4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1", 1);
+        assert!(info.score > defended + 50, "The undefended pawn is won");
+    }
+
+    #[test]
+    fn test_quiescence_line() {
+        let mut engine = Engine::new(1);
+        let board = fen_board("4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1");
+        let (score, line) = engine.quiescence(board.as_ref(), Color::White, eval::weights());
+        assert_eq!(
+            line,
+            vec![Move::new(Position::new('d', 1), Position::new('d', 5))]
+        );
+        let mut quiet = board.clone_as_a();
+        quiet.make_move(&line[0]).unwrap();
+        assert_eq!(score, -quiet.evaluate(&Color::Black));
 
-        fn square(&self, position: &Position) -> &crate::Square {
-            todo!()
+        let (score, line) = engine.quiescence(quiet.as_ref(), Color::Black, eval::weights());
+        assert!(line.is_empty(), "Nothing left to capture");
+        assert_eq!(score, quiet.evaluate(&Color::Black));
+    }
+
+    #[test]
+    fn test_search_options_by_name() {
+        let mut options = SearchOptions::default();
+        for name in SearchOptions::NAMES {
+            assert_eq!(options.get(name), Some(true), "{}", name);
+            assert!(options.set(name, false));
         }
+        assert_eq!(options, SearchOptions::none());
+        assert!(options.set("nullmove", true));
+        assert!(options.null_move);
+        assert!(!options.set("Contempt", true));
+        assert_eq!(options.get("Contempt"), None);
+    }
 
-        fn square_mut(&mut self, position: &Position) -> &mut crate::Square {
-            todo!()
+    #[test]
+    fn test_selective_search_keeps_results() {
+        let limits = SearchLimits {
+            depth: Some(4),
+            time: None,
+        };
+        let stop = AtomicBool::new(false);
+        let run = |options: SearchOptions, fen: &str| {
+            let fen: Fen = fen.parse().unwrap();
+            let mut engine = Engine::new(1);
+            engine.options = options;
+            engine.search(fen.board.as_ref(), fen.turn, &[], &limits, &stop, |_| {})
+        };
+
+        for fen in [
+            "k7/8/2K5/8/8/8/8/1R6 w - - 0 1",
+            "4k3/8/8/3q4/8/8/3R4/3RK3 w - - 0 1",
+        ] {
+            let plain = run(SearchOptions::none(), fen);
+            let selective = run(SearchOptions::default(), fen);
+            assert_eq!(selective.best_move(), plain.best_move(), "{}", fen);
+            assert_eq!(selective.score, plain.score, "{}", fen);
         }
+
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert!(
+            run(SearchOptions::default(), start).nodes < run(SearchOptions::none(), start).nodes,
+            "Pruning should shrink the tree"
+        );
+    }
+
+    #[test]
+    fn test_search_reports_every_depth() {
+        let board = board::new_board();
+        let limits = SearchLimits {
+            depth: Some(2),
+            time: None,
+        };
+        let mut depths = Vec::new();
+        let info = search(
+            &board,
+            Color::White,
+            &limits,
+            &AtomicBool::new(false),
+            |info| depths.push(info.depth),
+        );
+        assert_eq!(depths, vec![1, 2]);
+        assert_eq!(info.pv.len(), 2);
+    }
+
+    #[test]
+    fn test_search_stops_when_asked() {
+        let board = board::new_board();
+        let stop = AtomicBool::new(true);
+        let info = search(
+            &board,
+            Color::White,
+            &SearchLimits::default(),
+            &stop,
+            |_| {},
+        );
+        assert!(
+            info.best_move().is_some(),
+            "A stopped search still plays a move"
+        );
+    }
+
+    #[test]
+    fn test_search_reuses_transposition_table() {
+        let board = board::new_board();
+        let limits = SearchLimits {
+            depth: Some(3),
+            time: None,
+        };
+        let stop = AtomicBool::new(false);
+        let mut engine = Engine::new(1);
+        let first = engine.search(&board, Color::White, &[], &limits, &stop, |_| {});
+        let second = engine.search(&board, Color::White, &[], &limits, &stop, |_| {});
+        assert!(second.nodes < first.nodes);
+        assert_eq!(second.score, first.score);
+        assert!(engine.hashfull() > 0);
+
+        engine.clear();
+        assert_eq!(engine.hashfull(), 0);
+    }
+
+    #[test]
+    fn test_search_scores_repetition_as_draw() {
+        let fen = "7k/8/5K2/8/8/8/8/6Q1 b - - 0 1";
+        let only_move = Move::new(Position::new('h', 8), Position::new('h', 7));
+        let info = search_fen(fen, 1);
+        assert_eq!(info.best_move(), Some(only_move));
+        assert!(info.score < 0, "Black is a queen down");
+
+        let mut board = fen_board(fen);
+        board.make_move(&only_move).unwrap();
+        let history = [board.zobrist_key() ^ zobrist::side(Color::White)];
+        let limits = SearchLimits {
+            depth: Some(1),
+            time: None,
+        };
+        let info = Engine::new(1).search(
+            fen_board(fen).as_ref(),
+            Color::Black,
+            &history,
+            &limits,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        assert_eq!(info.score, 0, "Repeating the position is a draw");
+    }
+
+    #[test]
+    fn test_search_plays_book_moves() {
+        let board = board::new_board();
+        let e4 = Move::new(Position::new('e', 2), Position::new('e', 4));
+        let mut entry = book::hash(&board, Color::White).to_be_bytes().to_vec();
+        entry.extend([0x03, 0x1c, 0, 1, 0, 0, 0, 0]);
+        let mut engine = Engine::new(1);
+        engine.set_book(Some(Book::from_bytes(&entry).unwrap()));
+        engine.book_options.selection = book::Selection::Best;
+
+        let limits = SearchLimits {
+            depth: Some(3),
+            time: None,
+        };
+        let stop = AtomicBool::new(false);
+        let history = [board.zobrist_key()];
+        let info = engine.search(&board, Color::White, &history, &limits, &stop, |_| {});
+        assert_eq!(info.best_move(), Some(e4));
+        assert_eq!((info.depth, info.nodes), (0, 0));
+
+        engine.book_options.depth = 0;
+        let info = engine.search(&board, Color::White, &history, &limits, &stop, |_| {});
+        assert_eq!(info.depth, 3, "The book is not used past its depth");
     }
 }
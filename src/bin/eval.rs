@@ -0,0 +1,19 @@
+use chess::{Game, eval, fen::START_FEN};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let fen = if args.is_empty() {
+        START_FEN.to_string()
+    } else {
+        args.join(" ")
+    };
+    let game = match Game::from_fen(&fen) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Invalid FEN '{}': {}", fen, err);
+            std::process::exit(2);
+        }
+    };
+
+    println!("{}", eval::trace(game.board.as_ref()));
+}
@@ -0,0 +1,52 @@
+use std::time::Instant;
+
+use chess::{Game, fen::START_FEN};
+
+const USAGE: &str = "usage: perft [--divide] <depth> [fen]";
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let divide = match args.iter().position(|arg| arg == "--divide") {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    };
+
+    let Some(depth) = args.first().and_then(|depth| depth.parse::<u8>().ok()) else {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    };
+    let fen = match args.get(1..) {
+        Some(fen) if !fen.is_empty() => fen.join(" "),
+        _ => START_FEN.to_string(),
+    };
+    let game = match Game::from_fen(&fen) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Invalid FEN '{}': {}", fen, err);
+            std::process::exit(2);
+        }
+    };
+
+    let start = Instant::now();
+    let nodes = if divide {
+        let moves = game.board.divide(&game.turn, depth);
+        for (m, nodes) in moves.iter() {
+            println!("{}: {}", m, nodes);
+        }
+        println!();
+        moves.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        game.board.perft(&game.turn, depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("Nodes searched: {}", nodes);
+    println!(
+        "Time: {} ms ({} nps)",
+        elapsed.as_millis(),
+        (nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64
+    );
+}
@@ -0,0 +1,62 @@
+use std::fs;
+
+use chess::{eval::Weights, tuner};
+
+const USAGE: &str = "usage: tune <positions> [--output <file>] [--passes <n>] [--weights <file>]";
+const DEFAULT_OUTPUT: &str = "weights.txt";
+const DEFAULT_PASSES: usize = 100;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut positions = None;
+    let mut output = DEFAULT_OUTPUT.to_string();
+    let mut passes = DEFAULT_PASSES;
+    let mut weights = Weights::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = args.next().unwrap_or_else(|| fail(USAGE)),
+            "--passes" => {
+                passes = args
+                    .next()
+                    .and_then(|passes| passes.parse().ok())
+                    .unwrap_or_else(|| fail(USAGE))
+            }
+            "--weights" => {
+                let path = args.next().unwrap_or_else(|| fail(USAGE));
+                let text = fs::read_to_string(&path)
+                    .unwrap_or_else(|err| fail(&format!("Can not read '{}': {}", path, err)));
+                weights = text
+                    .parse()
+                    .unwrap_or_else(|err| fail(&format!("Invalid weights '{}': {}", path, err)));
+            }
+            _ if positions.is_none() => positions = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let Some(positions) = positions else {
+        fail(USAGE);
+    };
+
+    let text = fs::read_to_string(&positions)
+        .unwrap_or_else(|err| fail(&format!("Can not read '{}': {}", positions, err)));
+    let mut samples = tuner::load(&text, &weights)
+        .unwrap_or_else(|err| fail(&format!("Invalid positions '{}': {}", positions, err)));
+    println!("Loaded {} positions", samples.len());
+
+    let k = tuner::find_k(&mut samples, &weights);
+    let start = tuner::error(&mut samples, &weights, k);
+    println!("K: {:.3}, error: {:.6}", k, start);
+
+    tuner::tune(&mut samples, weights, k, passes, |pass, error, weights| {
+        println!("Pass {}: error {:.6}", pass, error);
+        if let Err(err) = fs::write(&output, weights.to_string()) {
+            fail(&format!("Can not write '{}': {}", output, err));
+        }
+    });
+    println!("Weights written to {}", output);
+}
@@ -0,0 +1,15 @@
+use std::io;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(path) = args
+        .windows(2)
+        .find(|pair| pair[0] == "--weights")
+        .map(|pair| &pair[1])
+        && let Err(err) = chess::eval::load_weights(path)
+    {
+        eprintln!("Invalid weights '{}': {}", path, err);
+        std::process::exit(2);
+    }
+    chess::uci::run(io::stdin().lock(), io::stdout());
+}
@@ -1,24 +1,51 @@
 use std::{fmt::Debug, ops::Range};
 
 use crate::{
-    Position, Square,
-    pieces::{self, ChessError, Color, Piece, PieceType, king},
+    Move, Position, Square,
+    ai::Score,
+    eval,
+    pieces::{self, ChessError, Color, Piece, PieceType, king, pawn},
+    zobrist,
 };
 
 pub const BOARD_SIZE: i32 = 8;
 pub const BOARD_SQUARES: i32 = BOARD_SIZE * BOARD_SIZE;
 
-pub trait BoardTrait: Debug + CloneAsBoard + 'static {
+pub trait BoardTrait: Debug + CloneAsBoard + Send + 'static {
     fn move_piece(&mut self, from: Position, to: Position)
     -> Result<Option<PieceType>, ChessError>;
+    fn make_move(&mut self, m: &Move) -> Result<Option<PieceType>, ChessError>;
     fn get_piece(&self, position: Position) -> Option<&PieceType>;
     fn get_all_white_pieces(&self) -> Vec<&PieceType>;
     fn get_all_black_pieces(&self) -> Vec<&PieceType>;
     fn is_king_check(&self, color: &Color) -> bool;
     fn can_king_move_safe_position(&self, color: &Color) -> bool;
-    fn evaluate(&self, color: &Color) -> i16;
+    fn legal_moves(&self, color: &Color) -> Vec<Move>;
+    fn piece_legal_moves(&self, position: &Position) -> Vec<Move>;
+    fn is_legal_move(&self, m: &Move) -> bool;
+    fn status(&self, color: &Color) -> GameStatus;
+    fn is_insufficient_material(&self) -> bool;
+    /// Zobrist key of the pieces, castling rights and en passant square, without the side
+    /// to move. It is updated incrementally by `make_move`.
+    fn zobrist_key(&self) -> u64;
+    /// Zobrist key of the pawns alone, which caches the pawn structure evaluation.
+    fn pawn_key(&self) -> u64;
+    /// Scores the position in centipawns for `color`.
+    fn evaluate(&self, color: &Color) -> Score;
     fn square(&self, position: &Position) -> &Square;
     fn square_mut(&mut self, position: &Position) -> &mut Square;
+    fn en_passant(&self) -> Option<Position>;
+    fn set_en_passant(&mut self, position: Option<Position>);
+    fn perft(&self, color: &Color, depth: u8) -> u64;
+    fn divide(&self, color: &Color, depth: u8) -> Vec<(Move, u64)>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameStatus {
+    Ongoing,
+    Check,
+    Checkmate,
+    Stalemate,
 }
 
 pub trait CloneAsBoard {
@@ -34,6 +61,11 @@ impl<T: 'static + BoardTrait + Clone> CloneAsBoard for T {
 #[derive(Debug, Clone)]
 struct Board {
     pub squares: Vec<Square>,
+    en_passant: Option<Position>,
+    /// Zobrist key of the board, `None` after it was edited square by square.
+    hash: Option<u64>,
+    /// Zobrist key of the pawns, `None` like `hash`.
+    pawn_hash: Option<u64>,
 }
 
 impl Board {
@@ -41,6 +73,7 @@ impl Board {
         squares[0].piece = Some(pieces::PieceType::Rook(
             pieces::Color::White,
             Position::new('a', 1),
+            true,
         ));
         squares[1].piece = Some(pieces::PieceType::Knight(
             pieces::Color::White,
@@ -57,6 +90,7 @@ impl Board {
         squares[4].piece = Some(pieces::PieceType::King(
             pieces::Color::White,
             Position::new('e', 1),
+            true,
         ));
         squares[5].piece = Some(pieces::PieceType::Bishop(
             pieces::Color::White,
@@ -69,9 +103,10 @@ impl Board {
         squares[7].piece = Some(pieces::PieceType::Rook(
             pieces::Color::White,
             Position::new('h', 1),
+            true,
         ));
-        for i in 8..16 {
-            squares[i].piece = Some(pieces::PieceType::Pawn(
+        for (i, square) in squares.iter_mut().enumerate().take(16).skip(8) {
+            square.piece = Some(pieces::PieceType::Pawn(
                 pieces::Color::White,
                 Position::new((i as i8 - 8 + 97) as u8 as char, 2),
                 true,
@@ -85,6 +120,7 @@ impl Board {
         squares[56].piece = Some(pieces::PieceType::Rook(
             pieces::Color::Black,
             Position::new('a', 8),
+            true,
         ));
         squares[57].piece = Some(pieces::PieceType::Knight(
             pieces::Color::Black,
@@ -101,6 +137,7 @@ impl Board {
         squares[60].piece = Some(pieces::PieceType::King(
             pieces::Color::Black,
             Position::new('e', 8),
+            true,
         ));
         squares[61].piece = Some(pieces::PieceType::Bishop(
             pieces::Color::Black,
@@ -113,9 +150,10 @@ impl Board {
         squares[63].piece = Some(pieces::PieceType::Rook(
             pieces::Color::Black,
             Position::new('h', 8),
+            true,
         ));
-        for i in 48..56 {
-            squares[i].piece = Some(pieces::PieceType::Pawn(
+        for (i, square) in squares.iter_mut().enumerate().take(56).skip(48) {
+            square.piece = Some(pieces::PieceType::Pawn(
                 pieces::Color::Black,
                 Position::new((i as i8 - 48 + 97) as u8 as char, 7),
                 true,
@@ -126,17 +164,66 @@ impl Board {
     }
 
     fn new_inner() -> Board {
-        let mut squares = Board::get_squares();
+        let squares = Board::get_squares();
 
         let squares = Board::fill_white(squares);
         let squares = Board::fill_black(squares);
-        Board { squares }
+        let mut board = Board {
+            squares,
+            en_passant: None,
+            hash: None,
+            pawn_hash: None,
+        };
+        board.hash = Some(zobrist::hash(&board));
+        board.pawn_hash = Some(zobrist::pawn_hash(&board));
+        board
     }
 
     fn empty_inner() -> Board {
         let squares = Board::get_squares();
 
-        Board { squares }
+        Board {
+            squares,
+            en_passant: None,
+            hash: Some(0),
+            pawn_hash: Some(0),
+        }
+    }
+
+    /// Returns the squares whose pieces change when `m` is played by `piece`: the rook's squares
+    /// when castling and the captured pawn's square when taking en passant.
+    fn touched_squares(piece: &PieceType, m: &Move) -> Vec<Position> {
+        let mut squares = vec![m.from, m.to];
+        match piece {
+            PieceType::King(_, _, _) if king::is_castling(&m.from, &m.to) => {
+                let (rook_from, rook_to) = king::castling_rook_positions(&m.to);
+                squares.push(rook_from);
+                squares.push(rook_to);
+            }
+            PieceType::Pawn(_, _, _) if m.from.x != m.to.x => {
+                squares.push(Position::new(m.to.x, m.from.y));
+            }
+            _ => {}
+        }
+        squares
+    }
+
+    /// Hashes the parts of the board that `make_move` can change around `squares`.
+    fn partial_hash(&self, squares: &[Position]) -> u64 {
+        let key = squares
+            .iter()
+            .filter_map(|position| self.get_piece(*position))
+            .fold(0, |key, piece| key ^ zobrist::piece(piece));
+        key ^ zobrist::castling(&castling_rights(self)) ^ zobrist::en_passant_target(self)
+    }
+
+    /// Hashes the pawns on `squares`.
+    fn partial_pawn_hash(&self, squares: &[Position]) -> u64 {
+        squares
+            .iter()
+            .filter_map(|position| self.get_piece(*position))
+            .filter(|piece| matches!(piece, PieceType::Pawn(_, _, _)))
+            .fold(0, |key, piece| key ^ zobrist::piece(piece))
     }
 
     fn get_squares() -> Vec<Square> {
@@ -162,13 +249,40 @@ impl BoardTrait for Board {
         from: Position,
         to: Position,
     ) -> Result<Option<PieceType>, ChessError> {
-        let from_index = from.to_index();
-        let piece = self.squares[from_index as usize].piece.take();
-        if piece.is_none() {
+        self.make_move(&Move::new(from, to))
+    }
+
+    fn make_move(&mut self, m: &Move) -> Result<Option<PieceType>, ChessError> {
+        let from_index = m.from.to_index();
+        let Some(moving) = self.squares[from_index as usize].piece else {
             return Err(ChessError::InvalidMove);
+        };
+        let hash = self.zobrist_key();
+        let touched = Board::touched_squares(&moving, m);
+        let before = self.partial_hash(&touched);
+        let pawn_hash = self.pawn_key();
+        let pawns_before = self.partial_pawn_hash(&touched);
+
+        let mut piece = moving;
+        self.squares[from_index as usize].piece = None;
+        let result = match piece {
+            PieceType::Pawn(_, _, _) => pawn::pawn_move_to(&piece, m.to, m.promotion, self),
+            _ => piece.move_to(m.to, self),
+        };
+        match result {
+            Ok(_) if !matches!(piece, PieceType::Pawn(_, _, _)) => self.en_passant = None,
+            Err(_) => self.squares[from_index as usize].piece = Some(piece),
+            _ => {}
         }
-        let board = piece.unwrap().move_to(to, self)?;
-        Ok(board)
+        self.hash = Some(match result {
+            Ok(_) => hash ^ before ^ self.partial_hash(&touched),
+            Err(_) => hash,
+        });
+        self.pawn_hash = Some(match result {
+            Ok(_) => pawn_hash ^ pawns_before ^ self.partial_pawn_hash(&touched),
+            Err(_) => pawn_hash,
+        });
+        result
     }
 
     fn get_piece(&self, position: Position) -> Option<&PieceType> {
@@ -179,10 +293,10 @@ impl BoardTrait for Board {
     fn get_all_white_pieces(&self) -> Vec<&PieceType> {
         let mut pieces = Vec::new();
         for square in &self.squares {
-            if let Some(piece) = &square.piece {
-                if piece.color() == Color::White {
-                    pieces.push(piece);
-                }
+            if let Some(piece) = &square.piece
+                && piece.color() == Color::White
+            {
+                pieces.push(piece);
             }
         }
 
@@ -192,10 +306,10 @@ impl BoardTrait for Board {
     fn get_all_black_pieces(&self) -> Vec<&PieceType> {
         let mut pieces = Vec::new();
         for square in &self.squares {
-            if let Some(piece) = &square.piece {
-                if piece.color() == Color::Black {
-                    pieces.push(piece);
-                }
+            if let Some(piece) = &square.piece
+                && piece.color() == Color::Black
+            {
+                pieces.push(piece);
             }
         }
 
@@ -209,7 +323,7 @@ impl BoardTrait for Board {
         };
 
         pieces.iter().any(|piece| {
-            if let PieceType::King(_, _) = piece {
+            if let PieceType::King(_, _, _) = piece {
                 return king::is_check(**piece, self);
             }
             false
@@ -223,31 +337,103 @@ impl BoardTrait for Board {
         };
 
         pieces.iter().any(|piece| {
-            if let PieceType::King(_, _) = piece {
+            if let PieceType::King(_, _, _) = piece {
                 return king::can_king_move_safe_position(**piece, self);
             }
             false
         })
     }
 
-    fn evaluate(&self, color: &Color) -> i16 {
-        let mut score = 0;
-        for square in &self.squares {
-            if let Some(piece) = &square.piece {
-                let value = piece.value() as i16;
-                if piece.color() == *color {
-                    score += value;
-                } else {
-                    score -= value;
-                }
+    fn legal_moves(&self, color: &Color) -> Vec<Move> {
+        let pieces = match color {
+            Color::White => self.get_all_white_pieces(),
+            Color::Black => self.get_all_black_pieces(),
+        };
+
+        pieces
+            .iter()
+            .flat_map(|piece| self.piece_legal_moves(piece.position()))
+            .collect()
+    }
+
+    fn piece_legal_moves(&self, position: &Position) -> Vec<Move> {
+        let Some(piece) = self.get_piece(*position) else {
+            return vec![];
+        };
+
+        piece
+            .possible_moves(self)
+            .into_iter()
+            .filter(|m| self.is_legal_move(m))
+            .collect()
+    }
+
+    fn is_legal_move(&self, m: &Move) -> bool {
+        let Some(piece) = self.get_piece(m.from) else {
+            return false;
+        };
+        let color = *piece.color();
+
+        let mut board = self.clone();
+        board.make_move(m).is_ok() && !board.is_king_check(&color)
+    }
+
+    fn status(&self, color: &Color) -> GameStatus {
+        let is_check = self.is_king_check(color);
+        let has_moves = !self.legal_moves(color).is_empty();
+
+        match (is_check, has_moves) {
+            (true, false) => GameStatus::Checkmate,
+            (false, false) => GameStatus::Stalemate,
+            (true, true) => GameStatus::Check,
+            (false, true) => GameStatus::Ongoing,
+        }
+    }
+
+    fn is_insufficient_material(&self) -> bool {
+        let pieces: Vec<&PieceType> = self
+            .squares
+            .iter()
+            .filter_map(|square| square.piece.as_ref())
+            .filter(|piece| !matches!(piece, PieceType::King(_, _, _)))
+            .collect();
+
+        match pieces.as_slice() {
+            [] => true,
+            [PieceType::Knight(_, _)] | [PieceType::Bishop(_, _)] => true,
+            _ => {
+                // Any number of bishops that all live on squares of the same color.
+                let square_colors: Vec<i8> = pieces
+                    .iter()
+                    .filter_map(|piece| match piece {
+                        PieceType::Bishop(_, position) => {
+                            Some((position.x as i8 - 'a' as i8 + position.y) % 2)
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                square_colors.len() == pieces.len()
+                    && square_colors.iter().all(|color| *color == square_colors[0])
             }
         }
+    }
+
+    fn zobrist_key(&self) -> u64 {
+        self.hash.unwrap_or_else(|| zobrist::hash(self))
+    }
+
+    fn pawn_key(&self) -> u64 {
+        self.pawn_hash.unwrap_or_else(|| zobrist::pawn_hash(self))
+    }
 
-        score
+    fn evaluate(&self, color: &Color) -> Score {
+        eval::evaluate(self, *color)
     }
 
     fn square_mut(&mut self, position: &Position) -> &mut Square {
         let index = position.to_index();
+        self.hash = None;
+        self.pawn_hash = None;
         &mut self.squares[index as usize]
     }
 
@@ -255,6 +441,79 @@ impl BoardTrait for Board {
         let index = position.to_index();
         &self.squares[index as usize]
     }
+
+    fn en_passant(&self) -> Option<Position> {
+        self.en_passant
+    }
+
+    fn set_en_passant(&mut self, position: Option<Position>) {
+        let before = zobrist::en_passant_target(self);
+        self.en_passant = position;
+        let after = zobrist::en_passant_target(self);
+        if let Some(hash) = self.hash.as_mut() {
+            *hash ^= before ^ after;
+        }
+    }
+
+    fn perft(&self, color: &Color, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves(color);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .iter()
+            .map(|m| {
+                let mut board = self.clone();
+                let _ = board.make_move(m);
+                board.perft(&color.opponent(), depth - 1)
+            })
+            .sum()
+    }
+
+    fn divide(&self, color: &Color, depth: u8) -> Vec<(Move, u64)> {
+        self.legal_moves(color)
+            .into_iter()
+            .map(|m| {
+                let mut board = self.clone();
+                let _ = board.make_move(&m);
+                let nodes = board.perft(&color.opponent(), depth.saturating_sub(1));
+                (m, nodes)
+            })
+            .collect()
+    }
+}
+
+/// Returns the castling rights left on the board as `KQkq`, or `-` when nobody can castle.
+pub fn castling_rights(board: &dyn BoardTrait) -> String {
+    let mut rights = String::new();
+    for (color, rank) in [(Color::White, 1), (Color::Black, 8)] {
+        let king_unmoved = matches!(
+            board.square(&Position::new('e', rank)).piece,
+            Some(PieceType::King(king_color, _, true)) if king_color == color
+        );
+        for (x, right) in [('h', 'K'), ('a', 'Q')] {
+            let rook_unmoved = matches!(
+                board.square(&Position::new(x, rank)).piece,
+                Some(PieceType::Rook(rook_color, _, true)) if rook_color == color
+            );
+            if king_unmoved && rook_unmoved {
+                rights.push(match color {
+                    Color::White => right,
+                    Color::Black => right.to_ascii_lowercase(),
+                });
+            }
+        }
+    }
+
+    if rights.is_empty() {
+        rights.push('-');
+    }
+    rights
 }
 
 pub fn new_board() -> impl BoardTrait {
@@ -269,6 +528,7 @@ pub fn empty_board() -> impl BoardTrait {
 mod test {
 
     use super::*;
+    use crate::fen::Fen;
 
     #[test]
     fn test_new_board_evealuate() {
@@ -286,7 +546,11 @@ mod test {
 
         let white_score = board.evaluate(&Color::White);
 
-        assert_eq!(white_score, -6);
+        assert!(
+            white_score < -500,
+            "Two knights are worth more than five pawns, got {}",
+            white_score
+        );
     }
 
     #[test]
@@ -299,7 +563,309 @@ mod test {
             board.squares[i].piece = None;
         }
 
-        assert_eq!(board.evaluate(&Color::White), -39);
+        let white_score = board.evaluate(&Color::White);
+        assert!(
+            white_score < -3000,
+            "A lone king is more than thirty pawns down, got {}",
+            white_score
+        );
+    }
+
+    #[test]
+    fn test_move_piece_castles() {
+        let mut board = Board::new_inner();
+        for x in ['f', 'g'] {
+            board.square_mut(&Position::new(x, 1)).piece = None;
+        }
+
+        let result = board.move_piece(Position::new('e', 1), Position::new('g', 1));
+        assert_eq!(result, Ok(None));
+        assert_eq!(
+            board.get_piece(Position::new('f', 1)),
+            Some(&PieceType::Rook(Color::White, Position::new('f', 1), false))
+        );
+    }
+
+    #[test]
+    fn test_move_piece_keeps_piece_on_invalid_move() {
+        let mut board = Board::new_inner();
+
+        let result = board.move_piece(Position::new('e', 1), Position::new('g', 1));
+        assert_eq!(result, Err(ChessError::BlockedMove));
+        assert!(board.get_piece(Position::new('e', 1)).is_some());
+    }
+
+    fn place(board: &mut Board, pieces: &[PieceType]) {
+        for piece in pieces {
+            board.square_mut(piece.position()).piece = Some(*piece);
+        }
+    }
+
+    #[test]
+    fn test_pinned_piece_has_no_legal_moves() {
+        let mut board = Board::empty_inner();
+        place(
+            &mut board,
+            &[
+                PieceType::King(Color::White, Position::new('e', 1), false),
+                PieceType::Knight(Color::White, Position::new('e', 3)),
+                PieceType::Rook(Color::Black, Position::new('e', 8), false),
+            ],
+        );
+
+        let knight = board.get_piece(Position::new('e', 3)).unwrap();
+        assert!(!knight.possible_moves(&board).is_empty());
+        assert!(board.piece_legal_moves(&Position::new('e', 3)).is_empty());
+    }
+
+    #[test]
+    fn test_legal_moves_evade_check() {
+        let mut board = Board::empty_inner();
+        place(
+            &mut board,
+            &[
+                PieceType::King(Color::White, Position::new('e', 1), false),
+                PieceType::Rook(Color::White, Position::new('a', 2), false),
+                PieceType::Rook(Color::Black, Position::new('e', 8), false),
+                PieceType::King(Color::Black, Position::new('h', 8), false),
+            ],
+        );
+
+        let moves = board.legal_moves(&Color::White);
+        assert_eq!(moves.len(), 5, "Four king moves and one block");
+        assert!(moves.contains(&Move::new(Position::new('a', 2), Position::new('e', 2))));
+        assert!(!moves.contains(&Move::new(Position::new('e', 1), Position::new('e', 2))));
+    }
+
+    #[test]
+    fn test_start_position_legal_moves() {
+        let board = new_board();
+        assert_eq!(board.legal_moves(&Color::White).len(), 20);
+        assert_eq!(board.legal_moves(&Color::Black).len(), 20);
+    }
+
+    const PERFT_POSITIONS: [(&str, &[u64]); 6] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902, 197281],
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862, 4085603],
+        ),
+        (
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238, 674624],
+        ),
+        (
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467, 422333],
+        ),
+        (
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379, 2103487],
+        ),
+        (
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890, 3894594],
+        ),
+    ];
+
+    /// Checks every reference position up to the deepest depth with at most `max_nodes` nodes.
+    fn check_perft(max_nodes: u64) {
+        for (fen, expected) in PERFT_POSITIONS {
+            let fen: Fen = fen.parse().unwrap();
+            for (depth, nodes) in expected.iter().enumerate() {
+                if *nodes > max_nodes {
+                    break;
+                }
+                let depth = depth as u8 + 1;
+                assert_eq!(
+                    fen.board.perft(&fen.turn, depth),
+                    *nodes,
+                    "perft({}) of {}",
+                    depth,
+                    fen
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft_reference_positions() {
+        check_perft(100_000);
+    }
+
+    /// Too slow for debug builds, run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_perft_reference_positions_deep() {
+        check_perft(u64::MAX);
+    }
+
+    #[test]
+    fn test_divide() {
+        let board = new_board();
+        let moves = board.divide(&Color::White, 3);
+        assert_eq!(moves.len(), 20);
+        assert_eq!(moves.iter().map(|(_, nodes)| nodes).sum::<u64>(), 8902);
+        assert!(moves.contains(&(Move::new(Position::new('e', 2), Position::new('e', 4)), 600)));
+    }
+
+    #[test]
+    fn test_king_can_not_capture_defended_piece() {
+        let mut board = Board::empty_inner();
+        place(
+            &mut board,
+            &[
+                PieceType::King(Color::White, Position::new('e', 1), false),
+                PieceType::Queen(Color::Black, Position::new('e', 2)),
+                PieceType::Rook(Color::Black, Position::new('e', 8), false),
+            ],
+        );
+
+        let capture = Move::new(Position::new('e', 1), Position::new('e', 2));
+        assert!(!board.is_legal_move(&capture));
+        assert!(board.legal_moves(&Color::White).is_empty());
+    }
+
+    #[test]
+    fn test_en_passant_exposing_king_is_illegal() {
+        let mut board = Board::empty_inner();
+        place(
+            &mut board,
+            &[
+                PieceType::King(Color::White, Position::new('a', 5), false),
+                PieceType::Pawn(Color::White, Position::new('b', 5), false),
+                PieceType::Pawn(Color::Black, Position::new('c', 5), false),
+                PieceType::Rook(Color::Black, Position::new('h', 5), false),
+            ],
+        );
+        board.set_en_passant(Some(Position::new('c', 6)));
+
+        assert_eq!(
+            board.piece_legal_moves(&Position::new('b', 5)),
+            vec![Move::new(Position::new('b', 5), Position::new('b', 6))]
+        );
+    }
+
+    #[test]
+    fn test_status_back_rank_mate() {
+        let mut board = Board::empty_inner();
+        place(
+            &mut board,
+            &[
+                PieceType::King(Color::Black, Position::new('g', 8), false),
+                PieceType::Pawn(Color::Black, Position::new('f', 7), false),
+                PieceType::Pawn(Color::Black, Position::new('g', 7), false),
+                PieceType::Pawn(Color::Black, Position::new('h', 7), false),
+                PieceType::Rook(Color::White, Position::new('d', 3), false),
+                PieceType::King(Color::White, Position::new('g', 2), false),
+            ],
+        );
+        assert_eq!(board.status(&Color::Black), GameStatus::Ongoing);
+
+        board
+            .move_piece(Position::new('d', 3), Position::new('d', 8))
+            .unwrap();
+        assert_eq!(board.status(&Color::Black), GameStatus::Checkmate);
+        assert_eq!(board.status(&Color::White), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn test_status_check_with_block() {
+        let mut board = Board::empty_inner();
+        place(
+            &mut board,
+            &[
+                PieceType::King(Color::White, Position::new('h', 1), false),
+                PieceType::Pawn(Color::White, Position::new('g', 2), false),
+                PieceType::Pawn(Color::White, Position::new('h', 2), false),
+                PieceType::Bishop(Color::White, Position::new('c', 4)),
+                PieceType::Rook(Color::Black, Position::new('a', 1), false),
+            ],
+        );
+
+        assert_eq!(
+            board.status(&Color::White),
+            GameStatus::Check,
+            "Back rank check can be blocked by the c4 bishop"
+        );
+    }
+
+    #[test]
+    fn test_status_stalemate() {
+        let mut board = Board::empty_inner();
+        place(
+            &mut board,
+            &[
+                PieceType::King(Color::Black, Position::new('e', 8), false),
+                PieceType::Pawn(Color::White, Position::new('e', 7), false),
+                PieceType::King(Color::White, Position::new('e', 6), false),
+            ],
+        );
+
+        assert_eq!(board.status(&Color::Black), GameStatus::Stalemate);
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        let kings = [
+            PieceType::King(Color::White, Position::new('e', 1), false),
+            PieceType::King(Color::Black, Position::new('e', 8), false),
+        ];
+
+        let mut board = Board::empty_inner();
+        place(&mut board, &kings);
+        assert!(board.is_insufficient_material(), "King against king");
+
+        place(
+            &mut board,
+            &[PieceType::Knight(Color::White, Position::new('b', 1))],
+        );
+        assert!(board.is_insufficient_material(), "King and knight");
+
+        let mut board = Board::empty_inner();
+        place(&mut board, &kings);
+        place(
+            &mut board,
+            &[
+                PieceType::Bishop(Color::White, Position::new('c', 1)),
+                PieceType::Bishop(Color::Black, Position::new('f', 4)),
+            ],
+        );
+        assert!(
+            board.is_insufficient_material(),
+            "Bishops on the same colored squares"
+        );
+
+        place(
+            &mut board,
+            &[PieceType::Bishop(Color::Black, Position::new('f', 5))],
+        );
+        assert!(
+            !board.is_insufficient_material(),
+            "Bishops on opposite colored squares"
+        );
+
+        let mut board = Board::empty_inner();
+        place(&mut board, &kings);
+        place(
+            &mut board,
+            &[PieceType::Pawn(Color::White, Position::new('a', 2), true)],
+        );
+        assert!(!board.is_insufficient_material(), "A pawn can promote");
+    }
+
+    #[test]
+    fn test_castling_rights() {
+        let mut board = Board::new_inner();
+        assert_eq!(castling_rights(&board), "KQkq");
+
+        board.square_mut(&Position::new('a', 8)).piece = None;
+        board.square_mut(&Position::new('e', 1)).piece =
+            Some(PieceType::King(Color::White, Position::new('e', 1), false));
+        assert_eq!(castling_rights(&board), "k");
     }
 
     #[test]
@@ -0,0 +1,643 @@
+use std::{fmt::Display, fs, str::FromStr, sync::OnceLock};
+
+use crate::{
+    ai::Score,
+    board::BoardTrait,
+    king_safety::{self, KingSafetyWeights},
+    mobility::{self, MobilityWeights},
+    pawns::{self, PawnStructure, PawnTable, PawnWeights},
+    pieces::{Color, Piece, PieceType},
+};
+
+/// Game phase of the starting position. Knights and bishops count one, rooks two and queens
+/// four, so the phase falls to zero once only kings and pawns are left.
+pub const MAX_PHASE: Score = 24;
+
+/// Material in the midgame and the endgame, indexed like `kind`.
+const MG_VALUE: [Score; 6] = [82, 337, 365, 477, 1025, 0];
+const EG_VALUE: [Score; 6] = [94, 281, 297, 512, 936, 0];
+const PHASE: [Score; 6] = [0, 1, 1, 2, 4, 0];
+
+// Piece-square tables from White's point of view, a8 first and h1 last.
+#[rustfmt::skip]
+const MG_PAWN: [Score; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     98, 134,  61,  95,  68, 126,  34, -11,
+     -6,   7,  26,  31,  65,  56,  25, -20,
+    -14,  13,   6,  21,  23,  12,  17, -23,
+    -27,  -2,  -5,  12,  17,   6,  10, -25,
+    -26,  -4,  -4, -10,   3,   3,  33, -12,
+    -35,  -1, -20, -23, -15,  24,  38, -22,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const EG_PAWN: [Score; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+    178, 173, 158, 134, 147, 132, 165, 187,
+     94, 100,  85,  67,  56,  53,  82,  84,
+     32,  24,  13,   5,  -2,   4,  17,  17,
+     13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+      4,   7,  -6,   1,   0,  -5,  -1,  -8,
+     13,   8,   8,  10,  13,   0,   2,  -7,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const MG_KNIGHT: [Score; 64] = [
+    -167, -89, -34, -49,  61, -97, -15, -107,
+     -73, -41,  72,  36,  23,  62,   7,  -17,
+     -47,  60,  37,  65,  84, 129,  73,   44,
+      -9,  17,  19,  53,  37,  69,  18,   22,
+     -13,   4,  16,  13,  28,  19,  21,   -8,
+     -23,  -9,  12,  10,  19,  17,  25,  -16,
+     -29, -53, -12,  -3,  -1,  18, -14,  -19,
+    -105, -21, -58, -33, -17, -28, -19,  -23,
+];
+
+#[rustfmt::skip]
+const EG_KNIGHT: [Score; 64] = [
+    -58, -38, -13, -28, -31, -27, -63, -99,
+    -25,  -8, -25,  -2,  -9, -25, -24, -52,
+    -24, -20,  10,   9,  -1,  -9, -19, -41,
+    -17,   3,  22,  22,  22,  11,   8, -18,
+    -18,  -6,  16,  25,  16,  17,   4, -18,
+    -23,  -3,  -1,  15,  10,  -3, -20, -22,
+    -42, -20, -10,  -5,  -2, -20, -23, -44,
+    -29, -51, -23, -15, -22, -18, -50, -64,
+];
+
+#[rustfmt::skip]
+const MG_BISHOP: [Score; 64] = [
+    -29,   4, -82, -37, -25, -42,   7,  -8,
+    -26,  16, -18, -13,  30,  59,  18, -47,
+    -16,  37,  43,  40,  35,  50,  37,  -2,
+     -4,   5,  19,  50,  37,  37,   7,  -2,
+     -6,  13,  13,  26,  34,  12,  10,   4,
+      0,  15,  15,  15,  14,  27,  18,  10,
+      4,  15,  16,   0,   7,  21,  33,   1,
+    -33,  -3, -14, -21, -13, -12, -39, -21,
+];
+
+#[rustfmt::skip]
+const EG_BISHOP: [Score; 64] = [
+    -14, -21, -11,  -8,  -7,  -9, -17, -24,
+     -8,  -4,   7, -12,  -3, -13,  -4, -14,
+      2,  -8,   0,  -1,  -2,   6,   0,   4,
+     -3,   9,  12,   9,  14,  10,   3,   2,
+     -6,   3,  13,  19,   7,  10,  -3,  -9,
+    -12,  -3,   8,  10,  13,   3,  -7, -15,
+    -14, -18,  -7,  -1,   4,  -9, -15, -27,
+    -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+];
+
+#[rustfmt::skip]
+const MG_ROOK: [Score; 64] = [
+     32,  42,  32,  51,  63,   9,  31,  43,
+     27,  32,  58,  62,  80,  67,  26,  44,
+     -5,  19,  26,  36,  17,  45,  61,  16,
+    -24, -11,   7,  26,  24,  35,  -8, -20,
+    -36, -26, -12,  -1,   9,  -7,   6, -23,
+    -45, -25, -16, -17,   3,   0,  -5, -33,
+    -44, -16, -20,  -9,  -1,  11,  -6, -71,
+    -19, -13,   1,  17,  16,   7, -37, -26,
+];
+
+#[rustfmt::skip]
+const EG_ROOK: [Score; 64] = [
+     13,  10,  18,  15,  12,  12,   8,   5,
+     11,  13,  13,  11,  -3,   3,   8,   3,
+      7,   7,   7,   5,   4,  -3,  -5,  -3,
+      4,   3,  13,   1,   2,   1,  -1,   2,
+      3,   5,   8,   4,  -5,  -6,  -8, -11,
+     -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+     -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+     -9,   2,   3,  -1,  -5, -13,   4, -20,
+];
+
+#[rustfmt::skip]
+const MG_QUEEN: [Score; 64] = [
+    -28,   0,  29,  12,  59,  44,  43,  45,
+    -24, -39,  -5,   1, -16,  57,  28,  54,
+    -13, -17,   7,   8,  29,  56,  47,  57,
+    -27, -27, -16, -16,  -1,  17,  -2,   1,
+     -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+    -14,   2, -11,  -2,  -5,   2,  14,   5,
+    -35,  -8,  11,   2,   8,  15,  -3,   1,
+     -1, -18,  -9,  10, -15, -25, -31, -50,
+];
+
+#[rustfmt::skip]
+const EG_QUEEN: [Score; 64] = [
+     -9,  22,  22,  27,  27,  19,  10,  20,
+    -17,  20,  32,  41,  58,  25,  30,   0,
+    -20,   6,   9,  49,  47,  35,  19,   9,
+      3,  22,  24,  45,  57,  40,  57,  36,
+    -18,  28,  19,  47,  31,  34,  39,  23,
+    -16, -27,  15,   6,   9,  17,  10,   5,
+    -22, -23, -30, -16, -16, -23, -36, -32,
+    -33, -28, -22, -43,  -5, -32, -20, -41,
+];
+
+#[rustfmt::skip]
+const MG_KING: [Score; 64] = [
+    -65,  23,  16, -15, -56, -34,   2,  13,
+     29,  -1, -20,  -7,  -8,  -4, -38, -29,
+     -9,  24,   2, -16, -20,   6,  22, -22,
+    -17, -20, -12, -27, -30, -25, -14, -36,
+    -49,  -1, -27, -39, -46, -44, -33, -51,
+    -14, -14, -22, -46, -44, -30, -15, -27,
+      1,   7,  -8, -64, -43, -16,   9,   8,
+    -15,  36,  12, -54,   8, -28,  24,  14,
+];
+
+#[rustfmt::skip]
+const EG_KING: [Score; 64] = [
+    -74, -35, -18, -18, -11,  15,   4, -17,
+    -12,  17,  14,  17,  17,  38,  23,  11,
+     10,  17,  23,  15,  20,  45,  44,  13,
+     -8,  22,  24,  27,  26,  33,  26,   3,
+    -18,  -4,  21,  24,  27,  23,   9, -11,
+    -19,  -3,  11,  21,  23,  16,   7,  -9,
+    -27, -11,   4,  13,  14,   4,  -5, -17,
+    -53, -34, -21, -11, -28, -14, -24, -43,
+];
+
+const KIND_NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+/// The weights the engine evaluates with, set once at startup by `set_weights`.
+static WEIGHTS: OnceLock<Weights> = OnceLock::new();
+
+/// Every tunable weight of the evaluation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weights {
+    /// Material of each piece kind, indexed like `kind`.
+    pub material_mg: [Score; 6],
+    pub material_eg: [Score; 6],
+    /// Piece-square tables of each piece kind from White's point of view, a8 first.
+    pub pst_mg: [[Score; 64]; 6],
+    pub pst_eg: [[Score; 64]; 6],
+    pub pawns: PawnWeights,
+    pub king_safety: KingSafetyWeights,
+    pub mobility: MobilityWeights,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            material_mg: MG_VALUE,
+            material_eg: EG_VALUE,
+            pst_mg: [MG_PAWN, MG_KNIGHT, MG_BISHOP, MG_ROOK, MG_QUEEN, MG_KING],
+            pst_eg: [EG_PAWN, EG_KNIGHT, EG_BISHOP, EG_ROOK, EG_QUEEN, EG_KING],
+            pawns: PawnWeights::default(),
+            king_safety: KingSafetyWeights::default(),
+            mobility: MobilityWeights::default(),
+        }
+    }
+}
+
+impl Weights {
+    /// Returns every weight grouped by name. The order is fixed, so the groups flatten into a
+    /// parameter vector, and the names identify the groups in a weights file.
+    pub fn groups_mut(&mut self) -> Vec<(String, &mut [Score])> {
+        let mut groups: Vec<(String, &mut [Score])> = vec![
+            ("material_mg".to_string(), &mut self.material_mg),
+            ("material_eg".to_string(), &mut self.material_eg),
+        ];
+        for (phase, tables) in [("mg", &mut self.pst_mg), ("eg", &mut self.pst_eg)] {
+            for (name, table) in KIND_NAMES.iter().zip(tables.iter_mut()) {
+                groups.push((format!("pst_{}_{}", phase, name), table));
+            }
+        }
+        let modules = [
+            self.pawns.groups_mut(),
+            self.king_safety.groups_mut(),
+            self.mobility.groups_mut(),
+        ];
+        for (name, values) in modules.into_iter().flatten() {
+            groups.push((name.to_string(), values));
+        }
+        groups
+    }
+
+    /// Returns all weights as one parameter vector.
+    pub fn params(&self) -> Vec<Score> {
+        let mut weights = self.clone();
+        weights
+            .groups_mut()
+            .into_iter()
+            .flat_map(|(_, values)| values.to_vec())
+            .collect()
+    }
+
+    /// Sets all weights from a parameter vector made by `params`.
+    pub fn set_params(&mut self, params: &[Score]) {
+        let mut params = params.iter();
+        for (_, values) in self.groups_mut() {
+            for value in values.iter_mut() {
+                *value = *params.next().expect("one parameter per weight");
+            }
+        }
+    }
+
+    /// Evaluates `board` in centipawns from the point of view of `color`, see `evaluate`.
+    pub fn evaluate(&self, board: &dyn BoardTrait, color: Color) -> Score {
+        from_white(self.trace(board).score(), color)
+    }
+
+    /// Evaluates like `evaluate`, looking the pawn structure up in `pawn_table`.
+    pub fn evaluate_cached(
+        &self,
+        board: &dyn BoardTrait,
+        color: Color,
+        pawn_table: &mut PawnTable,
+    ) -> Score {
+        let structure = pawn_table.get(board, &self.pawns);
+        from_white(self.trace_with(board, &structure).score(), color)
+    }
+
+    /// Evaluates `board` term by term, see `Trace`.
+    pub fn trace(&self, board: &dyn BoardTrait) -> Trace {
+        self.trace_with(board, &pawns::pawn_structure(board, &self.pawns))
+    }
+
+    fn trace_with(&self, board: &dyn BoardTrait, structure: &PawnStructure) -> Trace {
+        let mut terms = [[(0, 0); 2]; TERMS];
+        for (index, pieces) in [board.get_all_white_pieces(), board.get_all_black_pieces()]
+            .into_iter()
+            .enumerate()
+        {
+            for piece in pieces {
+                let kind = kind(piece);
+                let square = table_index(piece);
+                for (term, mg, eg) in [
+                    (
+                        Term::Material,
+                        self.material_mg[kind],
+                        self.material_eg[kind],
+                    ),
+                    (
+                        Term::PieceSquares,
+                        self.pst_mg[kind][square],
+                        self.pst_eg[kind][square],
+                    ),
+                ] {
+                    let score = &mut terms[term as usize][index];
+                    score.0 += mg;
+                    score.1 += eg;
+                }
+            }
+        }
+
+        let mobility = mobility::evaluate(board, &self.mobility);
+        terms[Term::PawnStructure as usize] = structure.score;
+        terms[Term::PassedPawns as usize] = pawns::passed_pawns(board, structure, &self.pawns);
+        terms[Term::KingSafety as usize] =
+            king_safety::evaluate(board, &mobility.king_attacks, &self.king_safety);
+        terms[Term::Mobility as usize] = mobility.score;
+
+        Trace {
+            terms,
+            phase: phase(board),
+        }
+    }
+}
+
+/// Writes one group of weights per line, its name followed by its values.
+impl Display for Weights {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut weights = self.clone();
+        for (name, values) in weights.groups_mut() {
+            write!(f, "{}", name)?;
+            for value in values.iter() {
+                write!(f, " {}", value)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads weights written by `Display`. Groups that are left out keep their default values,
+/// and lines starting with `#` are comments.
+impl FromStr for Weights {
+    type Err = WeightsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Weights::default();
+        let mut groups = weights.groups_mut();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let name = fields.next().unwrap_or_default();
+            let Some((_, values)) = groups.iter_mut().find(|(group, _)| group == name) else {
+                return Err(WeightsError::UnknownGroup(name.to_string()));
+            };
+            let parsed = fields
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| WeightsError::InvalidValue(value.to_string()))
+                })
+                .collect::<Result<Vec<Score>, _>>()?;
+            if parsed.len() != values.len() {
+                return Err(WeightsError::ValueCount(name.to_string(), parsed.len()));
+            }
+            values.copy_from_slice(&parsed);
+        }
+        drop(groups);
+        Ok(weights)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WeightsError {
+    UnknownGroup(String),
+    InvalidValue(String),
+    ValueCount(String, usize),
+    Io(String),
+    AlreadySet,
+}
+
+impl Display for WeightsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeightsError::UnknownGroup(name) => write!(f, "unknown weights '{}'", name),
+            WeightsError::InvalidValue(value) => write!(f, "'{}' is not a weight", value),
+            WeightsError::ValueCount(name, count) => {
+                write!(f, "wrong number of weights for '{}', found {}", name, count)
+            }
+            WeightsError::Io(err) => write!(f, "{}", err),
+            WeightsError::AlreadySet => write!(f, "the weights are already in use"),
+        }
+    }
+}
+
+impl std::error::Error for WeightsError {}
+
+/// Returns the weights the engine evaluates with, the defaults unless `set_weights` was called.
+pub fn weights() -> &'static Weights {
+    WEIGHTS.get_or_init(Weights::default)
+}
+
+/// Makes the engine evaluate with `weights`. This only works before the first evaluation.
+pub fn set_weights(weights: Weights) -> Result<(), WeightsError> {
+    WEIGHTS.set(weights).map_err(|_| WeightsError::AlreadySet)
+}
+
+/// Reads a weights file, such as one written by the tuner, and evaluates with it from now on.
+pub fn load_weights(path: &str) -> Result<(), WeightsError> {
+    let text = fs::read_to_string(path).map_err(|err| WeightsError::Io(err.to_string()))?;
+    set_weights(text.parse()?)
+}
+
+/// Index of a piece kind into the value and piece-square tables.
+fn kind(piece: &PieceType) -> usize {
+    match piece {
+        PieceType::Pawn(_, _, _) => 0,
+        PieceType::Knight(_, _) => 1,
+        PieceType::Bishop(_, _) => 2,
+        PieceType::Rook(_, _, _) => 3,
+        PieceType::Queen(_, _) => 4,
+        PieceType::King(_, _, _) => 5,
+    }
+}
+
+/// Index of the piece's square into the tables, mirrored vertically for Black.
+fn table_index(piece: &PieceType) -> usize {
+    let position = piece.position();
+    let file = (position.x as u8 - b'a') as usize;
+    let rank = match piece.color() {
+        Color::White => 8 - position.y as usize,
+        Color::Black => position.y as usize - 1,
+    };
+    rank * 8 + file
+}
+
+/// Returns the game phase of `board`, from `MAX_PHASE` in the opening down to zero in a pawn
+/// ending. Extra material from promotions is capped.
+pub fn phase(board: &dyn BoardTrait) -> Score {
+    let phase: Score = board
+        .get_all_white_pieces()
+        .into_iter()
+        .chain(board.get_all_black_pieces())
+        .map(|piece| PHASE[kind(piece)])
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+/// Blends a midgame and an endgame score by `phase`.
+pub fn taper(mg: Score, eg: Score, phase: Score) -> Score {
+    (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+/// A term of the evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Term {
+    Material,
+    PieceSquares,
+    PawnStructure,
+    PassedPawns,
+    KingSafety,
+    Mobility,
+}
+
+const TERMS: usize = 6;
+
+impl Term {
+    pub const ALL: [Term; TERMS] = [
+        Term::Material,
+        Term::PieceSquares,
+        Term::PawnStructure,
+        Term::PassedPawns,
+        Term::KingSafety,
+        Term::Mobility,
+    ];
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Material => write!(f, "Material"),
+            Term::PieceSquares => write!(f, "Piece squares"),
+            Term::PawnStructure => write!(f, "Pawn structure"),
+            Term::PassedPawns => write!(f, "Passed pawns"),
+            Term::KingSafety => write!(f, "King safety"),
+            Term::Mobility => write!(f, "Mobility"),
+        }
+    }
+}
+
+/// The evaluation of a position broken down by term, side and phase.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trace {
+    /// Midgame and endgame score of every term in `Term::ALL` order, for White and for Black,
+    /// each from its own side.
+    pub terms: [[(Score, Score); 2]; TERMS],
+    pub phase: Score,
+}
+
+impl Trace {
+    pub fn term(&self, term: Term) -> [(Score, Score); 2] {
+        self.terms[term as usize]
+    }
+
+    /// Returns the midgame and endgame score of White minus Black over all terms.
+    pub fn total(&self) -> (Score, Score) {
+        self.terms.iter().fold((0, 0), |(mg, eg), [white, black]| {
+            (mg + white.0 - black.0, eg + white.1 - black.1)
+        })
+    }
+
+    /// Returns the tapered score from White's point of view, which `evaluate` returns.
+    pub fn score(&self) -> Score {
+        let (mg, eg) = self.total();
+        taper(mg, eg, self.phase)
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let separator = "---------------+-------------+-------------+------------";
+        writeln!(f, "Term           |    White    |    Black    |    Total")?;
+        writeln!(
+            f,
+            "               |    MG    EG |    MG    EG |    MG    EG"
+        )?;
+        writeln!(f, "{}", separator)?;
+        let row =
+            |f: &mut std::fmt::Formatter<'_>, name: &str, [white, black]: [(Score, Score); 2]| {
+                writeln!(
+                    f,
+                    "{:<14} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                    name,
+                    white.0,
+                    white.1,
+                    black.0,
+                    black.1,
+                    white.0 - black.0,
+                    white.1 - black.1
+                )
+            };
+        for term in Term::ALL {
+            row(f, &term.to_string(), self.term(term))?;
+        }
+        writeln!(f, "{}", separator)?;
+        let white = self
+            .terms
+            .iter()
+            .fold((0, 0), |(mg, eg), [white, _]| (mg + white.0, eg + white.1));
+        let black = self
+            .terms
+            .iter()
+            .fold((0, 0), |(mg, eg), [_, black]| (mg + black.0, eg + black.1));
+        row(f, "Total", [white, black])?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        write!(f, "Final evaluation: {} (White side)", self.score())
+    }
+}
+
+/// Evaluates `board` in centipawns from the point of view of `color`, with material,
+/// piece-square tables, pawn structure, king safety and mobility tapered between midgame and
+/// endgame weights.
+pub fn evaluate(board: &dyn BoardTrait, color: Color) -> Score {
+    weights().evaluate(board, color)
+}
+
+/// Evaluates like `evaluate`, looking the pawn structure up in `pawn_table`.
+pub fn evaluate_cached(board: &dyn BoardTrait, color: Color, pawn_table: &mut PawnTable) -> Score {
+    weights().evaluate_cached(board, color, pawn_table)
+}
+
+fn from_white(score: Score, color: Color) -> Score {
+    match color {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// Evaluates `board` term by term, see `Trace`.
+pub fn trace(board: &dyn BoardTrait) -> Trace {
+    weights().trace(board)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{board, fen::Fen};
+
+    fn evaluate_fen(fen: &str) -> Score {
+        let fen: Fen = fen.parse().unwrap();
+        evaluate(fen.board.as_ref(), fen.turn)
+    }
+
+    #[test]
+    fn test_start_position_is_balanced() {
+        let board = board::new_board();
+        assert_eq!(phase(&board), MAX_PHASE);
+        assert_eq!(evaluate(&board, Color::White), 0);
+        assert_eq!(evaluate(&board, Color::Black), 0);
+    }
+
+    #[test]
+    fn test_colors_are_mirrored() {
+        let white = evaluate_fen("4k3/8/8/8/3N4/8/1P6/4K3 w - - 0 1");
+        let black = evaluate_fen("4k3/1p6/8/3n4/8/8/8/4K3 b - - 0 1");
+        assert_eq!(white, black);
+        assert!(white > 0);
+    }
+
+    #[test]
+    fn test_piece_square_tables() {
+        assert!(
+            evaluate_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1")
+                > evaluate_fen("4k3/8/8/8/8/8/8/N3K3 w - - 0 1"),
+            "A centralised knight is worth more than one in the corner"
+        );
+        assert!(
+            evaluate_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1")
+                > evaluate_fen("4k3/8/8/8/8/8/1P6/4K3 w - - 0 1"),
+            "An advanced pawn is worth more"
+        );
+    }
+
+    #[test]
+    fn test_trace_adds_up_to_evaluation() {
+        let fen: Fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let board = fen.board.as_ref();
+        let trace = trace(board);
+        assert_eq!(trace.score(), evaluate(board, Color::White));
+        assert_eq!(trace.phase, phase(board));
+        assert_eq!(trace.term(Term::Material), [(4039, 3868); 2]);
+
+        let printed = trace.to_string();
+        for term in Term::ALL {
+            assert!(printed.contains(&term.to_string()));
+        }
+        assert!(printed.ends_with(&format!("Final evaluation: {} (White side)", trace.score())));
+    }
+
+    #[test]
+    fn test_king_is_tapered_by_phase() {
+        let endgame = "4k3/8/8/8/4K3/8/8/8 w - - 0 1";
+        let fen: Fen = endgame.parse().unwrap();
+        assert_eq!(phase(fen.board.as_ref()), 0);
+        assert!(
+            evaluate_fen(endgame) > evaluate_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            "In the endgame the central king is better off"
+        );
+        assert!(
+            evaluate_fen("rnbqkbnr/8/8/8/4K3/8/8/RNBQ1BNR w - - 0 1")
+                < evaluate_fen("rnbqkbnr/8/8/8/8/8/8/RNBQKBNR w - - 0 1"),
+            "With pieces on the board the king stays home"
+        );
+        assert_eq!(taper(100, 0, MAX_PHASE), 100);
+        assert_eq!(taper(100, 0, 0), 0);
+        assert_eq!(taper(100, 0, MAX_PHASE / 2), 50);
+    }
+}
@@ -0,0 +1,454 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    Position,
+    board::{self, BoardTrait, castling_rights},
+    pieces::{Color, PieceType},
+};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// A position read from Forsyth–Edwards Notation.
+#[derive(Debug)]
+pub struct Fen {
+    pub board: Box<dyn BoardTrait>,
+    pub turn: Color,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl FromStr for Fen {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let mut board = Box::new(board::empty_board());
+        read_placement(fields[0], board.as_mut())?;
+
+        let turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            side => return Err(FenError::InvalidSideToMove(side.to_string())),
+        };
+
+        read_castling(fields[2], board.as_mut())?;
+
+        if fields[3] != "-" {
+            let en_passant = fields[3]
+                .parse::<Position>()
+                .map_err(|_| FenError::InvalidEnPassant(fields[3].to_string()))?;
+            let expected_rank = match turn {
+                Color::White => 6,
+                Color::Black => 3,
+            };
+            if en_passant.y != expected_rank {
+                return Err(FenError::InvalidEnPassant(fields[3].to_string()));
+            }
+            board.set_en_passant(Some(en_passant));
+        }
+
+        let (halfmove_clock, fullmove_number) = match fields.get(4..6) {
+            Some([halfmove_clock, fullmove_number]) => (
+                halfmove_clock
+                    .parse::<u32>()
+                    .map_err(|_| FenError::InvalidHalfmoveClock(halfmove_clock.to_string()))?,
+                fullmove_number
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|number| *number > 0)
+                    .ok_or_else(|| FenError::InvalidFullmoveNumber(fullmove_number.to_string()))?,
+            ),
+            _ => (0, 1),
+        };
+
+        Ok(Fen {
+            board,
+            turn,
+            halfmove_clock,
+            fullmove_number,
+        })
+    }
+}
+
+impl Display for Fen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            to_fen(
+                self.board.as_ref(),
+                &self.turn,
+                self.halfmove_clock,
+                self.fullmove_number
+            )
+        )
+    }
+}
+
+/// Writes the full FEN record of `board` with the given side to move and move counters.
+pub fn to_fen(
+    board: &dyn BoardTrait,
+    turn: &Color,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+) -> String {
+    let side = match turn {
+        Color::White => 'w',
+        Color::Black => 'b',
+    };
+    let en_passant = board
+        .en_passant()
+        .map_or("-".to_string(), |position| position.to_string());
+
+    format!(
+        "{} {} {} {} {} {}",
+        placement(board),
+        side,
+        castling_rights(board),
+        en_passant,
+        halfmove_clock,
+        fullmove_number
+    )
+}
+
+/// Writes the piece placement field, from the eighth rank down to the first.
+pub fn placement(board: &dyn BoardTrait) -> String {
+    let mut placement = String::new();
+    for y in (1..=8).rev() {
+        let mut empty = 0;
+        for x in 'a'..='h' {
+            match board.square(&Position::new(x, y)).piece {
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    placement.push(piece.symbol());
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+        if y > 1 {
+            placement.push('/');
+        }
+    }
+
+    placement
+}
+
+fn read_placement(placement: &str, board: &mut dyn BoardTrait) -> Result<(), FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::RankCount(ranks.len()));
+    }
+
+    for (rank, y) in ranks.iter().zip((1..=8).rev()) {
+        let mut file = 0;
+        for c in rank.chars() {
+            if let Some(empty) = c.to_digit(10).filter(|empty| (1..=8).contains(empty)) {
+                file += empty as i8;
+                continue;
+            }
+            if file >= 8 {
+                return Err(FenError::RankLength(y));
+            }
+
+            let position = Position::try_from((file as u8, y as u8 - 1))
+                .map_err(|_| FenError::RankLength(y))?;
+            board.square_mut(&position).piece = Some(piece_from_symbol(c, position)?);
+            file += 1;
+        }
+        if file != 8 {
+            return Err(FenError::RankLength(y));
+        }
+    }
+
+    for color in [Color::White, Color::Black] {
+        let pieces = match color {
+            Color::White => board.get_all_white_pieces(),
+            Color::Black => board.get_all_black_pieces(),
+        };
+        let kings = pieces
+            .iter()
+            .filter(|piece| matches!(piece, PieceType::King(_, _, _)))
+            .count();
+        if kings != 1 {
+            return Err(FenError::KingCount(color, kings));
+        }
+        if let Some(pawn) = pieces.iter().find(|piece| {
+            matches!(piece, PieceType::Pawn(_, position, _) if position.y == 1 || position.y == 8)
+        }) {
+            return Err(FenError::PawnOnBackRank(*pawn.position()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Pawns keep their double step on their starting rank, kings and rooks start as moved and
+/// only regain their castling flags from the castling field.
+fn piece_from_symbol(symbol: char, position: Position) -> Result<PieceType, FenError> {
+    let color = if symbol.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let piece = match symbol.to_ascii_lowercase() {
+        'p' => {
+            let start_rank = match color {
+                Color::White => 2,
+                Color::Black => 7,
+            };
+            PieceType::Pawn(color, position, position.y == start_rank)
+        }
+        'r' => PieceType::Rook(color, position, false),
+        'n' => PieceType::Knight(color, position),
+        'b' => PieceType::Bishop(color, position),
+        'q' => PieceType::Queen(color, position),
+        'k' => PieceType::King(color, position, false),
+        _ => return Err(FenError::InvalidPiece(symbol)),
+    };
+
+    Ok(piece)
+}
+
+fn read_castling(castling: &str, board: &mut dyn BoardTrait) -> Result<(), FenError> {
+    if castling == "-" {
+        return Ok(());
+    }
+    if castling.is_empty() {
+        return Err(FenError::InvalidCastling(castling.to_string()));
+    }
+
+    for right in castling.chars() {
+        let (color, rank) = match right {
+            'K' | 'Q' => (Color::White, 1),
+            'k' | 'q' => (Color::Black, 8),
+            _ => return Err(FenError::InvalidCastling(castling.to_string())),
+        };
+        let x = match right.to_ascii_lowercase() {
+            'k' => 'h',
+            _ => 'a',
+        };
+
+        let king_position = Position::new('e', rank);
+        let rook_position = Position::new(x, rank);
+        match (
+            board.square(&king_position).piece,
+            board.square(&rook_position).piece,
+        ) {
+            (Some(PieceType::King(king_color, _, _)), Some(PieceType::Rook(rook_color, _, _)))
+                if king_color == color && rook_color == color =>
+            {
+                board.square_mut(&king_position).piece =
+                    Some(PieceType::King(color, king_position, true));
+                board.square_mut(&rook_position).piece =
+                    Some(PieceType::Rook(color, rook_position, true));
+            }
+            _ => return Err(FenError::InvalidCastling(castling.to_string())),
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FenError {
+    FieldCount(usize),
+    RankCount(usize),
+    RankLength(i8),
+    InvalidPiece(char),
+    KingCount(Color, usize),
+    PawnOnBackRank(Position),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::FieldCount(count) => {
+                write!(f, "expected 4 or 6 space separated fields, found {}", count)
+            }
+            FenError::RankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::RankLength(rank) => write!(f, "rank {} does not describe 8 squares", rank),
+            FenError::InvalidPiece(c) => write!(f, "'{}' is not a piece letter", c),
+            FenError::KingCount(color, count) => {
+                write!(f, "{} must have exactly one king, found {}", color, count)
+            }
+            FenError::PawnOnBackRank(position) => {
+                write!(
+                    f,
+                    "pawn on {} can not stand on the first or last rank",
+                    position
+                )
+            }
+            FenError::InvalidSideToMove(side) => {
+                write!(f, "'{}' is not a side to move, expected w or b", side)
+            }
+            FenError::InvalidCastling(castling) => write!(
+                f,
+                "'{}' is not a valid castling field for this placement",
+                castling
+            ),
+            FenError::InvalidEnPassant(square) => {
+                write!(f, "'{}' is not a valid en passant square", square)
+            }
+            FenError::InvalidHalfmoveClock(clock) => {
+                write!(f, "'{}' is not a valid halfmove clock", clock)
+            }
+            FenError::InvalidFullmoveNumber(number) => {
+                write!(f, "'{}' is not a valid fullmove number", number)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Game, Move};
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn test_start_position_round_trip() {
+        let fen: Fen = START_FEN.parse().unwrap();
+        assert_eq!(fen.to_string(), START_FEN);
+        assert_eq!(
+            fen.board.zobrist_key(),
+            board::new_board().zobrist_key(),
+            "Start FEN should describe the same board as new_board"
+        );
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        for record in [
+            KIWIPETE,
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "4k3/8/8/8/8/8/8/4K2R b K - 12 40",
+        ] {
+            let fen: Fen = record.parse().unwrap();
+            assert_eq!(fen.to_string(), record);
+        }
+    }
+
+    #[test]
+    fn test_counters_are_optional() {
+        let fen: Fen = "4k3/8/8/8/8/8/8/4K3 b - -".parse().unwrap();
+        assert_eq!(fen.turn, Color::Black);
+        assert_eq!(fen.halfmove_clock, 0);
+        assert_eq!(fen.fullmove_number, 1);
+    }
+
+    #[test]
+    fn test_castling_field_sets_flags() {
+        let fen: Fen = "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1".parse().unwrap();
+        let moves = fen.board.legal_moves(&Color::White);
+        assert!(moves.contains(&Move::new(Position::new('e', 1), Position::new('g', 1))));
+        assert!(!moves.contains(&Move::new(Position::new('e', 1), Position::new('c', 1))));
+
+        let moves = fen.board.legal_moves(&Color::Black);
+        assert!(moves.contains(&Move::new(Position::new('e', 8), Position::new('c', 8))));
+        assert!(!moves.contains(&Move::new(Position::new('e', 8), Position::new('g', 8))));
+    }
+
+    #[test]
+    fn test_en_passant_field() {
+        let fen: Fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 3".parse().unwrap();
+        let moves = fen.board.legal_moves(&Color::White);
+        assert!(moves.contains(&Move::new(Position::new('e', 5), Position::new('d', 6))));
+    }
+
+    #[test]
+    fn test_pawn_double_step_only_from_start_rank() {
+        let fen: Fen = "4k3/8/8/8/8/4P3/3P4/4K3 w - - 0 1".parse().unwrap();
+        let moves = fen.board.legal_moves(&Color::White);
+        assert!(moves.contains(&Move::new(Position::new('d', 2), Position::new('d', 4))));
+        assert!(!moves.contains(&Move::new(Position::new('e', 3), Position::new('e', 5))));
+    }
+
+    #[test]
+    fn test_malformed_fen() {
+        let cases = [
+            ("8/8/8/8/8/8/8/8 w", FenError::FieldCount(2)),
+            ("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::RankCount(7)),
+            ("4k3/8/8/8/8/8/8/4K4 w - - 0 1", FenError::RankLength(1)),
+            ("4k3/8/8/8/8/8/8/4K2 w - - 0 1", FenError::RankLength(1)),
+            ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", FenError::InvalidPiece('X')),
+            (
+                "4k3/8/8/8/8/8/8/8 w - - 0 1",
+                FenError::KingCount(Color::White, 0),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/P3K3 w - - 0 1",
+                FenError::PawnOnBackRank(Position::new('a', 1)),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+                FenError::InvalidCastling("K".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+                FenError::InvalidEnPassant("e3".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - -1 1",
+                FenError::InvalidHalfmoveClock("-1".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                FenError::InvalidFullmoveNumber("0".to_string()),
+            ),
+        ];
+
+        for (record, error) in cases {
+            assert_eq!(
+                record.parse::<Fen>().err(),
+                Some(error),
+                "'{}' should be rejected",
+                record
+            );
+        }
+    }
+
+    #[test]
+    fn test_game_from_fen() {
+        let mut game =
+            Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 7 30").expect("FEN should be valid");
+        assert_eq!(game.turn, Color::Black);
+        assert_eq!(game.halfmove_clock, 7);
+
+        game.make_move(&Move::new(Position::new('e', 8), Position::new('d', 8)))
+            .unwrap();
+        assert_eq!(game.to_fen(), "3k4/8/8/8/8/8/4P3/4K3 w - - 8 31");
+
+        game.make_move(&Move::new(Position::new('e', 2), Position::new('e', 4)))
+            .unwrap();
+        assert_eq!(game.to_fen(), "3k4/8/8/8/4P3/8/8/4K3 b - e3 0 31");
+    }
+
+    #[test]
+    fn test_new_game_to_fen() {
+        assert_eq!(Game::new().to_fen(), START_FEN);
+    }
+}
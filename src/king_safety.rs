@@ -0,0 +1,244 @@
+use std::slice;
+
+use crate::{
+    Position,
+    ai::Score,
+    board::BoardTrait,
+    pieces::{Color, Piece, PieceType},
+};
+
+/// Weights of the king safety terms, which are all midgame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KingSafetyWeights {
+    /// Penalty for a file next to the king whose shield pawn has moved two squares, or is
+    /// missing altogether.
+    pub shield_advanced: Score,
+    pub shield_missing: Score,
+    /// Penalty for a file next to the king without a pawn of its own, and the extra penalty
+    /// when the enemy has none there either.
+    pub semi_open_file: Score,
+    pub open_file: Score,
+    /// Weight of a knight, bishop, rook and queen attacking the king zone.
+    pub attack_weight: [Score; 4],
+    /// Percentage of the attack weight that counts, by the number of attacking pieces. A lone
+    /// attacker is rarely dangerous.
+    pub attacker_scale: [Score; 8],
+}
+
+impl Default for KingSafetyWeights {
+    fn default() -> Self {
+        KingSafetyWeights {
+            shield_advanced: -10,
+            shield_missing: -25,
+            semi_open_file: -15,
+            open_file: -10,
+            attack_weight: [20, 20, 40, 80],
+            attacker_scale: [0, 0, 50, 75, 88, 94, 97, 99],
+        }
+    }
+}
+
+impl KingSafetyWeights {
+    /// Returns every weight by name, see `eval::Weights::groups_mut`.
+    pub fn groups_mut(&mut self) -> Vec<(&'static str, &mut [Score])> {
+        vec![
+            (
+                "shield_advanced",
+                slice::from_mut(&mut self.shield_advanced),
+            ),
+            ("shield_missing", slice::from_mut(&mut self.shield_missing)),
+            ("semi_open_file", slice::from_mut(&mut self.semi_open_file)),
+            ("open_file", slice::from_mut(&mut self.open_file)),
+            ("attack_weight", &mut self.attack_weight),
+            ("attacker_scale", &mut self.attacker_scale),
+        ]
+    }
+}
+
+/// Pieces of one side attacking the zone around the enemy king.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KingAttacks {
+    /// Number of attacking knights, bishops, rooks and queens.
+    pub attackers: [usize; 4],
+}
+
+impl KingAttacks {
+    /// Counts `piece` as an attacker if any of `targets` lies in `zone`.
+    pub fn add(&mut self, piece: &PieceType, targets: &[Position], zone: &[Position]) {
+        let kind = match piece {
+            PieceType::Knight(_, _) => 0,
+            PieceType::Bishop(_, _) => 1,
+            PieceType::Rook(_, _, _) => 2,
+            PieceType::Queen(_, _) => 3,
+            _ => return,
+        };
+        if targets.iter().any(|target| zone.contains(target)) {
+            self.attackers[kind] += 1;
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.attackers.iter().sum()
+    }
+
+    /// Returns the midgame penalty of the king under these attacks.
+    pub fn penalty(&self, weights: &KingSafetyWeights) -> Score {
+        let weight: Score = self
+            .attackers
+            .iter()
+            .zip(weights.attack_weight)
+            .map(|(attackers, weight)| *attackers as Score * weight)
+            .sum();
+        -weight * weights.attacker_scale[self.count().min(7)] / 100
+    }
+}
+
+/// Returns the king of `color`, if it is on the board.
+pub fn king(board: &dyn BoardTrait, color: Color) -> Option<Position> {
+    let pieces = match color {
+        Color::White => board.get_all_white_pieces(),
+        Color::Black => board.get_all_black_pieces(),
+    };
+    pieces
+        .into_iter()
+        .find(|piece| matches!(piece, PieceType::King(_, _, _)))
+        .map(|king| *king.position())
+}
+
+/// Returns the squares around the king, the king's own square included.
+pub fn king_zone(king: &Position) -> Vec<Position> {
+    (-1..=1)
+        .flat_map(|df| (-1..=1).map(move |dr| (df, dr)))
+        .filter_map(|(df, dr)| king.offset(df, dr))
+        .collect()
+}
+
+/// Scores the pawn shelter of the king of `color` on its own and neighbouring files.
+pub fn shelter(
+    board: &dyn BoardTrait,
+    color: Color,
+    king: &Position,
+    weights: &KingSafetyWeights,
+) -> Score {
+    let forward = match color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    let is_pawn = |position: Option<Position>, pawn_color: Color| {
+        position
+            .and_then(|position| board.get_piece(position))
+            .is_some_and(|piece| {
+                matches!(piece, PieceType::Pawn(_, _, _)) && *piece.color() == pawn_color
+            })
+    };
+
+    let mut score = 0;
+    for df in -1..=1 {
+        let Some(file) = king.offset(df, 0) else {
+            continue;
+        };
+        score += if is_pawn(file.offset(0, forward), color) {
+            0
+        } else if is_pawn(file.offset(0, 2 * forward), color) {
+            weights.shield_advanced
+        } else {
+            weights.shield_missing
+        };
+
+        let on_file = |pawn_color: Color| {
+            (1..=8).any(|y| is_pawn(Some(Position::new(file.x, y)), pawn_color))
+        };
+        if !on_file(color) {
+            score += weights.semi_open_file;
+            if !on_file(color.opponent()) {
+                score += weights.open_file;
+            }
+        }
+    }
+    score
+}
+
+/// Scores the safety of the white and of the black king. `attacks` holds the attacks of White
+/// on the black king and of Black on the white king, in that order. King safety only matters
+/// while there are pieces to attack with, so it is all midgame.
+pub fn evaluate(
+    board: &dyn BoardTrait,
+    attacks: &[KingAttacks; 2],
+    weights: &KingSafetyWeights,
+) -> [(Score, Score); 2] {
+    let mut score = [(0, 0); 2];
+    for (index, color) in [Color::White, Color::Black].into_iter().enumerate() {
+        if let Some(king) = king(board, color) {
+            score[index].0 =
+                shelter(board, color, &king, weights) + attacks[1 - index].penalty(weights);
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{board, fen::Fen};
+
+    fn shelter_fen(fen: &str, color: Color) -> Score {
+        let fen: Fen = fen.parse().unwrap();
+        let board = fen.board.as_ref();
+        shelter(
+            board,
+            color,
+            &king(board, color).unwrap(),
+            &KingSafetyWeights::default(),
+        )
+    }
+
+    #[test]
+    fn test_start_position_is_balanced() {
+        let board = board::new_board();
+        let score = evaluate(
+            &board,
+            &[KingAttacks::default(); 2],
+            &KingSafetyWeights::default(),
+        );
+        assert_eq!(score[0], score[1]);
+    }
+
+    #[test]
+    fn test_pawn_shield() {
+        let w = KingSafetyWeights::default();
+        let castled = shelter_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", Color::White);
+        assert_eq!(castled, 0);
+        assert_eq!(
+            shelter_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", Color::Black),
+            0
+        );
+
+        let pushed = shelter_fen("6k1/5ppp/8/8/8/6P1/5P1P/6K1 w - - 0 1", Color::White);
+        assert_eq!(pushed, w.shield_advanced);
+
+        let missing = shelter_fen("6k1/5p1p/8/8/8/8/5P1P/6K1 w - - 0 1", Color::White);
+        assert_eq!(missing, w.shield_missing + w.semi_open_file + w.open_file);
+        let semi_open = shelter_fen("6k1/5ppp/8/8/8/8/5P1P/6K1 w - - 0 1", Color::White);
+        assert_eq!(semi_open, w.shield_missing + w.semi_open_file);
+    }
+
+    #[test]
+    fn test_attackers() {
+        let w = KingSafetyWeights::default();
+        let zone = king_zone(&Position::new('g', 8));
+        assert_eq!(zone.len(), 6);
+
+        let queen = PieceType::Queen(Color::White, Position::new('d', 4));
+        let knight = PieceType::Knight(Color::White, Position::new('e', 5));
+        let mut attacks = KingAttacks::default();
+        attacks.add(&queen, &[Position::new('h', 8)], &zone);
+        assert_eq!(attacks.penalty(&w), 0, "A lone attacker is ignored");
+        attacks.add(&knight, &[Position::new('c', 6)], &zone);
+        assert_eq!(attacks.attackers, [0, 0, 0, 1]);
+        attacks.add(&knight, &[Position::new('f', 7)], &zone);
+        assert_eq!(
+            attacks.penalty(&w),
+            -(w.attack_weight[0] + w.attack_weight[3]) * w.attacker_scale[2] / 100
+        );
+    }
+}
@@ -1,22 +1,55 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-use board::BoardTrait;
-use pieces::{Color, PieceType};
+use board::{BOARD_SQUARES, BoardTrait, GameStatus};
+use fen::{Fen, FenError};
+use pgn::PgnError;
+use pieces::{ChessError, Color, Piece, PieceType, Promotion, Value};
+use san::SanError;
 
 pub mod ai;
 pub mod board;
+pub mod book;
+pub mod eval;
+pub mod fen;
+pub mod king_safety;
+pub mod mobility;
+pub mod ordering;
+pub mod pawns;
+pub mod pgn;
 pub mod pieces;
+pub mod san;
+pub mod tt;
+pub mod tuner;
+pub mod uci;
+pub mod xboard;
+pub mod zobrist;
 
 #[derive(Debug)]
 pub struct Game {
     pub board: Box<dyn BoardTrait>,
     pub white: Player,
     pub black: Player,
+    pub turn: Color,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    pub initial_fen: String,
+    /// Zobrist keys of every position of the game, used to detect repetitions.
+    pub position_history: Vec<u64>,
+    pub draw_rules: DrawRules,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Self {
-        let board = board::new_board();
+        Game::from_board(Box::new(board::new_board()), Color::White)
+    }
+
+    pub fn from_board(board: Box<dyn BoardTrait>, turn: Color) -> Self {
         let white = Player {
             color: Color::White,
             moves: Vec::new(),
@@ -28,21 +61,208 @@ impl Game {
             captured_pieces: Vec::new(),
         };
 
-        Game {
-            board: Box::new(board),
+        let mut game = Game {
+            board,
             white,
             black,
+            turn,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            initial_fen: String::new(),
+            position_history: Vec::new(),
+            draw_rules: DrawRules::default(),
+        };
+        game.initial_fen = game.to_fen();
+        game.position_history.push(game.position_key());
+        game
+    }
+
+    /// Starts a game from a FEN record, keeping its side to move and move counters.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fen: Fen = fen.parse()?;
+        let mut game = Game::from_board(fen.board, fen.turn);
+        game.halfmove_clock = fen.halfmove_clock;
+        game.fullmove_number = fen.fullmove_number;
+        game.initial_fen = game.to_fen();
+        Ok(game)
+    }
+
+    pub fn to_fen(&self) -> String {
+        fen::to_fen(
+            self.board.as_ref(),
+            &self.turn,
+            self.halfmove_clock,
+            self.fullmove_number,
+        )
+    }
+
+    /// Reads a move for the side to move, either in SAN such as `Nf3` or `exd5` or as
+    /// coordinates such as `e2e4` or `e7e8n`.
+    pub fn parse_move(&self, input: &str) -> Result<Move, SanError> {
+        let input = input.trim();
+        if let (Some(from), Some(to)) = (input.get(0..2), input.get(2..4))
+            && let (Ok(from), Ok(to)) = (from.parse::<Position>(), to.parse::<Position>())
+        {
+            let promotion = match input.get(4..) {
+                Some("") | None => None,
+                Some(promotion) => {
+                    let mut chars = promotion.chars();
+                    match (chars.next().and_then(Promotion::from_char), chars.next()) {
+                        (Some(promotion), None) => Some(promotion),
+                        _ => return Err(SanError::InvalidSyntax(input.to_string())),
+                    }
+                }
+            };
+            let legal_moves = self.board.legal_moves(&self.turn);
+            return find_move(&legal_moves, from, to, promotion)
+                .ok_or_else(|| SanError::IllegalMove(input.to_string()));
+        }
+
+        san::parse_san(self.board.as_ref(), &self.turn, input)
+    }
+
+    /// Returns the moves played so far in SAN, numbered like a scoresheet.
+    pub fn scoresheet(&self) -> String {
+        let mut replay = Game::from_fen(&self.initial_fen).expect("initial FEN is always valid");
+        let mut tokens = Vec::new();
+        for (ply, m) in self.moves().iter().enumerate() {
+            if replay.turn == Color::White {
+                tokens.push(format!("{}.", replay.fullmove_number));
+            } else if ply == 0 {
+                tokens.push(format!("{}...", replay.fullmove_number));
+            }
+            tokens.push(san::to_san(replay.board.as_ref(), m));
+            replay
+                .make_move(m)
+                .expect("moves of a game are always legal");
+        }
+
+        tokens.join(" ")
+    }
+
+    /// Writes the game as PGN with unknown tag values.
+    pub fn to_pgn(&self) -> String {
+        pgn::write(self, &pgn::Tags::default())
+    }
+
+    /// Replays the first game of a PGN file.
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnError> {
+        pgn::read(pgn).map(|(_, game)| game)
+    }
+
+    /// Takes back the last move by replaying the game without it.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mut moves = self.moves();
+        let last = moves.pop()?;
+
+        let mut game = Game::from_fen(&self.initial_fen).expect("initial FEN is always valid");
+        game.draw_rules = self.draw_rules;
+        for m in moves.iter() {
+            game.make_move(m).expect("moves of a game are always legal");
+        }
+        *self = game;
+
+        Some(last)
+    }
+
+    /// Returns every move played so far in order, alternating between the two players.
+    pub fn moves(&self) -> Vec<Move> {
+        let (first, second) = match self.initial_fen.split_whitespace().nth(1) {
+            Some("b") => (&self.black.moves, &self.white.moves),
+            _ => (&self.white.moves, &self.black.moves),
+        };
+
+        let mut moves = Vec::with_capacity(first.len() + second.len());
+        for (index, m) in first.iter().enumerate() {
+            moves.push(*m);
+            if let Some(m) = second.get(index) {
+                moves.push(*m);
+            }
+        }
+        moves
+    }
+
+    pub fn player(&self, color: Color) -> &Player {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    pub fn make_move(&mut self, m: &Move) -> Result<Option<PieceType>, ChessError> {
+        let Some(piece) = self.board.get_piece(m.from).copied() else {
+            return Err(ChessError::NoPiece);
+        };
+        if piece.color() != self.turn || !self.board.is_legal_move(m) {
+            return Err(ChessError::InvalidMove);
+        }
+
+        let captured = self.board.make_move(m)?;
+
+        let player = match self.turn {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        };
+        player.moves.push(*m);
+        if let Some(captured) = captured {
+            player.captured_pieces.push(captured);
+        }
+
+        if matches!(piece, PieceType::Pawn(_, _, _)) || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if self.turn == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.turn = self.turn.opponent();
+        self.position_history.push(self.position_key());
+
+        Ok(captured)
+    }
+
+    /// Returns the Zobrist key of the current position, including the side to move.
+    pub fn position_key(&self) -> u64 {
+        self.board.zobrist_key() ^ zobrist::side(self.turn)
+    }
+
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        if self.board.is_insufficient_material() {
+            return Some(DrawReason::InsufficientMaterial);
         }
+
+        if self.draw_rules.seventy_five_move_rule {
+            if self.halfmove_clock >= 150 {
+                return Some(DrawReason::SeventyFiveMoveRule);
+            }
+        } else if self.halfmove_clock >= 100 {
+            return Some(DrawReason::FiftyMoveRule);
+        }
+
+        let current = self.position_key();
+        let repetitions = self
+            .position_history
+            .iter()
+            .filter(|key| **key == current)
+            .count();
+        if self.draw_rules.fivefold_repetition {
+            if repetitions >= 5 {
+                return Some(DrawReason::FivefoldRepetition);
+            }
+        } else if repetitions >= 3 {
+            return Some(DrawReason::ThreefoldRepetition);
+        }
+
+        None
     }
 
     pub fn play(&mut self) {
         let game = self;
-        let mut turn = Color::White;
         loop {
-            let player = match turn {
-                Color::White => &mut game.white,
-                Color::Black => &mut game.black,
-            };
+            let turn = game.turn;
+            let player = game.player(turn);
 
             let captured = &player.captured_pieces;
             if !captured.is_empty() {
@@ -50,69 +270,100 @@ impl Game {
                 captured.iter().for_each(|p| {
                     print!("{}, ", p);
                 });
-                println!("");
+                println!();
             }
 
-            player.moves.iter().for_each(|m| {
-                print!("{}, ", m);
-            });
-            println!("");
-            if game.board.is_king_check(&turn) {
-                if game.board.can_king_move_safe_position(&turn) {
-                    println!("{} king is in checkmate", turn);
+            let scoresheet = game.scoresheet();
+            if !scoresheet.is_empty() {
+                println!("{}", scoresheet);
+            }
+            match game.board.status(&turn) {
+                GameStatus::Checkmate => {
+                    println!("{} king is in checkmate, {} wins", turn, turn.opponent());
+                    break;
+                }
+                GameStatus::Stalemate => {
+                    println!(
+                        "{} has no legal moves, the game is drawn by stalemate",
+                        turn
+                    );
                     break;
                 }
-                println!("{:?} king is in check", turn);
+                GameStatus::Check => println!("{} king is in check", turn),
+                GameStatus::Ongoing => {}
+            }
+
+            if let Some(reason) = game.draw_reason() {
+                println!("The game is drawn by {}", reason);
+                break;
             }
 
             let mut input = String::new();
-            println!("{} turn", player.color);
-            println!("Enter move: ");
+            println!("{} turn", turn);
+            println!("Enter move (e.g. Nf3, exd5, O-O or e2e4): ");
             std::io::stdin().read_line(&mut input).unwrap();
-            let input = input.trim();
-            let from = Position::new(
-                input.chars().nth(0).unwrap(),
-                input.chars().nth(1).unwrap().to_digit(10).unwrap() as i8,
-            );
-            let to = Position::new(
-                input.chars().nth(2).unwrap(),
-                input.chars().nth(3).unwrap().to_digit(10).unwrap() as i8,
-            );
-            let result = game.board.clone_as_a().move_piece(from, to);
-            if result.is_err() {
+            let m = match game.parse_move(&input) {
+                Ok(m) => m,
+                Err(err) => {
+                    println!("Invalid move: {}", err);
+                    continue;
+                }
+            };
+
+            if game.make_move(&m).is_err() {
                 println!("Invalid move");
-                continue;
             }
+        }
+    }
+}
 
-            let captured = result.unwrap();
-            match turn {
-                Color::Black => {
-                    if game.board.is_king_check(&Color::Black) {
-                        println!("Invalid move, Black king is in check");
-                        continue;
-                    }
-                }
-                Color::White => {
-                    if game.board.is_king_check(&Color::White) {
-                        println!("Invalid move, White king is in check");
-                        continue;
-                    }
-                }
-            }
+/// Optional draw rules. By default a game is drawn automatically after fifty moves without a
+/// capture or pawn move and on the third repetition of a position.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DrawRules {
+    /// Play on past fifty moves and only declare the draw after seventy-five.
+    pub seventy_five_move_rule: bool,
+    /// Play on past the third repetition and only declare the draw on the fifth.
+    pub fivefold_repetition: bool,
+}
 
-            player.moves.push(Move { from, to });
-            if let Some(captured) = captured {
-                player.captured_pieces.push(captured);
-            }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawReason {
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    InsufficientMaterial,
+}
 
-            turn = match turn {
-                Color::White => Color::Black,
-                Color::Black => Color::White,
-            };
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::FiftyMoveRule => write!(f, "the fifty-move rule"),
+            DrawReason::SeventyFiveMoveRule => write!(f, "the seventy-five-move rule"),
+            DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawReason::FivefoldRepetition => write!(f, "fivefold repetition"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material"),
         }
     }
 }
 
+/// Picks the legal move matching the entered squares, promoting to a queen when no piece is given.
+fn find_move(
+    legal_moves: &[Move],
+    from: Position,
+    to: Position,
+    promotion: Option<Promotion>,
+) -> Option<Move> {
+    let promotion = promotion.or(Some(Promotion::Queen));
+    legal_moves
+        .iter()
+        .find(|m| {
+            m.from == from && m.to == to && (m.promotion.is_none() || m.promotion == promotion)
+        })
+        .copied()
+}
+
 #[derive(Debug, Clone)]
 pub struct Player {
     pub color: Color,
@@ -121,23 +372,46 @@ pub struct Player {
 }
 
 impl Player {
-    pub fn get_total_value(&self) -> u8 {
+    pub fn get_total_value(&self) -> Value {
         self.captured_pieces
             .iter()
             .fold(0, |acc, piece| acc + piece.value())
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Move {
     pub from: Position,
     pub to: Position,
+    pub promotion: Option<Promotion>,
+}
+
+impl Move {
+    pub fn new(from: Position, to: Position) -> Self {
+        Move {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    pub fn with_promotion(from: Position, to: Position, promotion: Promotion) -> Self {
+        Move {
+            from,
+            to,
+            promotion: Some(promotion),
+        }
+    }
 }
 
 impl Display for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", self.from.x, self.from.y)?;
-        write!(f, "{}{}", self.to.x, self.to.y)
+        write!(f, "{}{}", self.to.x, self.to.y)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_char())?;
+        }
+        Ok(())
     }
 }
 
@@ -150,10 +424,7 @@ pub struct Square {
 
 impl Clone for Square {
     fn clone(&self) -> Self {
-        let piece = match &self.piece {
-            Some(piece) => Some(piece.clone()),
-            None => None,
-        };
+        let piece = self.piece;
 
         Square {
             piece,
@@ -163,7 +434,7 @@ impl Clone for Square {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Position {
     pub x: char,
     pub y: i8,
@@ -171,7 +442,7 @@ pub struct Position {
 
 impl Position {
     pub fn new(x: char, y: i8) -> Self {
-        if x < 'a' || x > 'h' || y < 1 || y > 8 {
+        if !('a'..='h').contains(&x) || !(1..=8).contains(&y) {
             panic!("Invalid position: {},{}", x, y);
         }
         Position { x, y }
@@ -184,7 +455,7 @@ impl Position {
     }
 
     pub fn from_index(index: i32) -> Self {
-        if index < 0 || index > 64 {
+        if !(0..BOARD_SQUARES).contains(&index) {
             panic!("Invalid index: {}", index);
         }
 
@@ -195,16 +466,159 @@ impl Position {
             y: y as i8,
         }
     }
+
+    /// Zero based file, `0` for the a-file up to `7` for the h-file.
+    pub fn file(&self) -> i8 {
+        self.x as i8 - 'a' as i8
+    }
+
+    /// Zero based rank, `0` for the first rank up to `7` for the eighth rank.
+    pub fn rank(&self) -> i8 {
+        self.y - 1
+    }
+
+    /// Returns the position `df` files and `dr` ranks away, or `None` when it falls off the board.
+    pub fn offset(&self, df: i8, dr: i8) -> Option<Position> {
+        let file = self.file() + df;
+        let rank = self.rank() + dr;
+        if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+            return None;
+        }
+
+        Some(Position {
+            x: (b'a' + file as u8) as char,
+            y: rank + 1,
+        })
+    }
+
+    /// Number of king steps between the two positions.
+    pub fn distance(&self, other: &Position) -> i8 {
+        let df = (self.file() - other.file()).abs();
+        let dr = (self.rank() - other.rank()).abs();
+        df.max(dr)
+    }
+
+    /// Iterates the positions in the `(df, dr)` direction, up to the edge of the board.
+    pub fn ray(&self, df: i8, dr: i8) -> Ray {
+        Ray {
+            current: *self,
+            df,
+            dr,
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.x, self.y)
+    }
+}
+
+impl FromStr for Position {
+    type Err = PositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let (Some(x), Some(y), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err(PositionError::InvalidLength(s.to_string()));
+        };
+        if !('a'..='h').contains(&x) {
+            return Err(PositionError::InvalidFile(x));
+        }
+        match y.to_digit(10) {
+            Some(rank @ 1..=8) => Ok(Position { x, y: rank as i8 }),
+            _ => Err(PositionError::InvalidRank(y)),
+        }
+    }
+}
+
+impl TryFrom<&str> for Position {
+    type Error = PositionError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Builds a position from zero based `(file, rank)` coordinates.
+impl TryFrom<(u8, u8)> for Position {
+    type Error = PositionError;
+
+    fn try_from((file, rank): (u8, u8)) -> Result<Self, Self::Error> {
+        if file >= 8 || rank >= 8 {
+            return Err(PositionError::OutOfBoard(file, rank));
+        }
+
+        Ok(Position {
+            x: (b'a' + file) as char,
+            y: rank as i8 + 1,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PositionError {
+    InvalidLength(String),
+    InvalidFile(char),
+    InvalidRank(char),
+    OutOfBoard(u8, u8),
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PositionError::InvalidLength(s) => {
+                write!(f, "'{}' is not a square, expected a file and a rank", s)
+            }
+            PositionError::InvalidFile(x) => write!(f, "'{}' is not a file between a and h", x),
+            PositionError::InvalidRank(y) => write!(f, "'{}' is not a rank between 1 and 8", y),
+            PositionError::OutOfBoard(file, rank) => {
+                write!(f, "({}, {}) is outside of the board", file, rank)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+#[derive(Debug, Clone)]
+pub struct Ray {
+    current: Position,
+    df: i8,
+    dr: i8,
+}
+
+impl Iterator for Ray {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.current.offset(self.df, self.dr)?;
+        self.current = next;
+        Some(next)
+    }
 }
 
 #[cfg(test)]
 mod test {
 
     use crate::{
-        BoardTrait, Position, board,
-        pieces::{Color, PieceType},
+        BoardTrait, DrawReason, Game, Move, Position, PositionError, board,
+        pieces::{ChessError, Color, PieceType, Promotion},
+        san::SanError,
     };
 
+    fn shuffle_knights(game: &mut Game) {
+        for (from, to) in [
+            (('g', 1), ('f', 3)),
+            (('g', 8), ('f', 6)),
+            (('f', 3), ('g', 1)),
+            (('f', 6), ('g', 8)),
+        ] {
+            let m = Move::new(Position::new(from.0, from.1), Position::new(to.0, to.1));
+            game.make_move(&m).unwrap();
+        }
+    }
+
     #[test]
     fn test_position_to_index() {
         let position = Position::new('a', 1);
@@ -220,25 +634,25 @@ mod test {
     #[test]
     fn test_black_king_check() {
         let mut board = board::empty_board();
-        let black_king = PieceType::King(Color::Black, Position::new('e', 8));
+        let black_king = PieceType::King(Color::Black, Position::new('e', 8), true);
         let white_queen = PieceType::Queen(Color::White, Position::new('f', 7));
         board.square_mut(&Position::new('e', 8)).piece = Some(black_king);
         board.square_mut(&Position::new('f', 7)).piece = Some(white_queen);
 
         let is_check = board.is_king_check(&Color::Black);
-        assert_eq!(is_check, true);
+        assert!(is_check);
     }
 
     #[test]
     fn test_king_not_check() {
         let mut board = board::empty_board();
-        let king = PieceType::King(Color::White, Position::new('e', 6));
+        let king = PieceType::King(Color::White, Position::new('e', 6), false);
         let black_queen = PieceType::Queen(Color::Black, Position::new('f', 8));
         board.square_mut(&Position::new('e', 6)).piece = Some(king);
         board.square_mut(&Position::new('f', 8)).piece = Some(black_queen);
 
         let is_check = board.is_king_check(&Color::White);
-        assert_eq!(is_check, false);
+        assert!(!is_check);
     }
 
     #[test]
@@ -275,4 +689,224 @@ mod test {
         assert_eq!(position.x, 'd');
         assert_eq!(position.y, 1);
     }
+
+    #[test]
+    fn test_move_display() {
+        let m = Move::new(Position::new('e', 2), Position::new('e', 4));
+        assert_eq!(m.to_string(), "e2e4");
+
+        let m = Move::with_promotion(
+            Position::new('e', 7),
+            Position::new('e', 8),
+            Promotion::Knight,
+        );
+        assert_eq!(m.to_string(), "e7e8n");
+    }
+
+    #[test]
+    fn test_game_make_move_checks_turn() {
+        let mut game = Game::new();
+        let m = Move::new(Position::new('e', 7), Position::new('e', 5));
+        assert_eq!(game.make_move(&m), Err(ChessError::InvalidMove));
+
+        let m = Move::new(Position::new('e', 2), Position::new('e', 4));
+        assert_eq!(game.make_move(&m), Ok(None));
+        assert_eq!(game.turn, Color::Black);
+        assert_eq!(game.white.moves, vec![m]);
+    }
+
+    #[test]
+    fn test_game_parse_move() {
+        let mut game = Game::new();
+        let m = Move::new(Position::new('g', 1), Position::new('f', 3));
+        assert_eq!(game.parse_move("Nf3\n"), Ok(m));
+        assert_eq!(game.parse_move("g1f3"), Ok(m));
+        assert_eq!(
+            game.parse_move("e2e5"),
+            Err(SanError::IllegalMove("e2e5".to_string()))
+        );
+        assert_eq!(
+            game.parse_move("e2e4x"),
+            Err(SanError::InvalidSyntax("e2e4x".to_string()))
+        );
+        assert_eq!(
+            game.parse_move("Nf6"),
+            Err(SanError::IllegalMove("Nf6".to_string()))
+        );
+
+        game.make_move(&m).unwrap();
+        assert_eq!(
+            game.parse_move("Nf6"),
+            Ok(Move::new(Position::new('g', 8), Position::new('f', 6)))
+        );
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 3 10").unwrap();
+        assert_eq!(game.undo(), None);
+
+        let fen = game.to_fen();
+        let m = Move::new(Position::new('e', 2), Position::new('e', 4));
+        game.make_move(&m).unwrap();
+        assert_eq!(game.undo(), Some(m));
+        assert_eq!(game.to_fen(), fen);
+        assert!(game.white.moves.is_empty());
+        assert_eq!(game.position_history.len(), 1);
+    }
+
+    #[test]
+    fn test_scoresheet() {
+        let mut game = Game::new();
+        assert_eq!(game.scoresheet(), "");
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"] {
+            let m = game.parse_move(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        assert_eq!(
+            game.scoresheet(),
+            "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. O-O"
+        );
+
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R b K - 0 12").unwrap();
+        assert_eq!(game.scoresheet(), "");
+        let mut game = game;
+        for san in ["Kd7", "O-O+"] {
+            let m = game.parse_move(san).unwrap();
+            game.make_move(&m).unwrap();
+        }
+        assert_eq!(game.scoresheet(), "12... Kd7 13. O-O");
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut game = Game::new();
+        shuffle_knights(&mut game);
+        assert_eq!(game.draw_reason(), None);
+        shuffle_knights(&mut game);
+        assert_eq!(game.draw_reason(), Some(DrawReason::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn test_repetition_after_a_double_pawn_push() {
+        let mut game = Game::new();
+        for (from, to) in [
+            (('e', 2), ('e', 4)),
+            (('g', 8), ('f', 6)),
+            (('g', 1), ('f', 3)),
+            (('f', 6), ('g', 8)),
+            (('f', 3), ('g', 1)),
+            (('g', 8), ('f', 6)),
+            (('g', 1), ('f', 3)),
+            (('f', 6), ('g', 8)),
+        ] {
+            let m = Move::new(Position::new(from.0, from.1), Position::new(to.0, to.1));
+            game.make_move(&m).unwrap();
+        }
+        assert_eq!(game.draw_reason(), None);
+        let m = Move::new(Position::new('f', 3), Position::new('g', 1));
+        game.make_move(&m).unwrap();
+        assert_eq!(game.draw_reason(), Some(DrawReason::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn test_fivefold_repetition() {
+        let mut game = Game::new();
+        game.draw_rules.fivefold_repetition = true;
+        for _ in 0..3 {
+            shuffle_knights(&mut game);
+        }
+        assert_eq!(game.draw_reason(), None);
+        shuffle_knights(&mut game);
+        assert_eq!(game.draw_reason(), Some(DrawReason::FivefoldRepetition));
+    }
+
+    #[test]
+    fn test_fifty_move_rule() {
+        let mut game = Game::new();
+        game.halfmove_clock = 99;
+        let m = Move::new(Position::new('g', 1), Position::new('f', 3));
+        game.make_move(&m).unwrap();
+        assert_eq!(game.halfmove_clock, 100);
+        assert_eq!(game.draw_reason(), Some(DrawReason::FiftyMoveRule));
+
+        game.draw_rules.seventy_five_move_rule = true;
+        assert_eq!(game.draw_reason(), None);
+        game.halfmove_clock = 150;
+        assert_eq!(game.draw_reason(), Some(DrawReason::SeventyFiveMoveRule));
+
+        let m = Move::new(Position::new('e', 7), Position::new('e', 5));
+        game.make_move(&m).unwrap();
+        assert_eq!(game.halfmove_clock, 0, "Pawn moves reset the clock");
+    }
+
+    #[test]
+    fn test_insufficient_material_draw() {
+        let mut board = board::empty_board();
+        for king in [
+            PieceType::King(Color::White, Position::new('e', 1), false),
+            PieceType::King(Color::Black, Position::new('e', 8), false),
+        ] {
+            board.square_mut(king.position()).piece = Some(king);
+        }
+
+        let game = Game::from_board(Box::new(board), Color::White);
+        assert_eq!(game.draw_reason(), Some(DrawReason::InsufficientMaterial));
+    }
+
+    #[test]
+    fn test_position_from_str() {
+        assert_eq!("e4".parse::<Position>(), Ok(Position::new('e', 4)));
+        assert_eq!(Position::try_from("h8"), Ok(Position::new('h', 8)));
+        assert_eq!(
+            "i4".parse::<Position>(),
+            Err(PositionError::InvalidFile('i'))
+        );
+        assert_eq!(
+            "a9".parse::<Position>(),
+            Err(PositionError::InvalidRank('9'))
+        );
+        assert_eq!(
+            "e".parse::<Position>(),
+            Err(PositionError::InvalidLength("e".to_string()))
+        );
+        assert_eq!(
+            "e44".parse::<Position>(),
+            Err(PositionError::InvalidLength("e44".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_position_try_from_coordinates() {
+        assert_eq!(Position::try_from((0, 0)), Ok(Position::new('a', 1)));
+        assert_eq!(Position::try_from((4, 3)), Ok(Position::new('e', 4)));
+        assert_eq!(
+            Position::try_from((8, 0)),
+            Err(PositionError::OutOfBoard(8, 0))
+        );
+        assert_eq!(Position::new('e', 4).to_string(), "e4");
+    }
+
+    #[test]
+    fn test_position_offset_and_distance() {
+        let position = Position::new('e', 4);
+        assert_eq!(position.file(), 4);
+        assert_eq!(position.rank(), 3);
+        assert_eq!(position.offset(1, 2), Some(Position::new('f', 6)));
+        assert_eq!(position.offset(-4, -3), Some(Position::new('a', 1)));
+        assert_eq!(position.offset(-5, 0), None);
+        assert_eq!(Position::new('h', 1).offset(1, 0), None);
+        assert_eq!(position.distance(&Position::new('a', 8)), 4);
+        assert_eq!(position.distance(&position), 0);
+    }
+
+    #[test]
+    fn test_position_ray() {
+        let ray: Vec<Position> = Position::new('c', 1).ray(1, 1).collect();
+        assert_eq!(ray.len(), 5);
+        assert_eq!(ray.first(), Some(&Position::new('d', 2)));
+        assert_eq!(ray.last(), Some(&Position::new('h', 6)));
+
+        assert_eq!(Position::new('a', 4).ray(-1, 0).count(), 0);
+    }
 }
@@ -1,6 +1,33 @@
 use chess::Game;
 
 fn main() {
-    let mut game = Game::new();
+    let mut args = std::env::args().skip(1);
+    let mut fen = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "--weights" {
+            let Some(path) = args.next() else {
+                eprintln!("usage: chess [<fen>] [--weights <file>]");
+                std::process::exit(2);
+            };
+            if let Err(err) = chess::eval::load_weights(&path) {
+                eprintln!("Invalid weights '{}': {}", path, err);
+                std::process::exit(2);
+            }
+        } else {
+            fen.push(arg);
+        }
+    }
+    let mut game = if fen.is_empty() {
+        Game::new()
+    } else {
+        let fen = fen.join(" ");
+        match Game::from_fen(&fen) {
+            Ok(game) => game,
+            Err(err) => {
+                eprintln!("Invalid FEN '{}': {}", fen, err);
+                std::process::exit(1);
+            }
+        }
+    };
     game.play();
 }
@@ -0,0 +1,129 @@
+use crate::{
+    ai::Score,
+    board::BoardTrait,
+    king_safety::{self, KingAttacks},
+    pieces::{Color, Piece, PieceType},
+};
+
+/// Number of reachable squares of an average piece, which scores zero.
+const MOBILITY_BASE: [Score; 4] = [4, 7, 7, 14];
+
+/// Weights of the mobility terms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MobilityWeights {
+    /// Bonus per reachable square, for knights, bishops, rooks and queens.
+    pub mg: [Score; 4],
+    pub eg: [Score; 4],
+}
+
+impl Default for MobilityWeights {
+    fn default() -> Self {
+        MobilityWeights {
+            mg: [4, 5, 2, 1],
+            eg: [4, 5, 4, 2],
+        }
+    }
+}
+
+impl MobilityWeights {
+    /// Returns every weight by name, see `eval::Weights::groups_mut`.
+    pub fn groups_mut(&mut self) -> Vec<(&'static str, &mut [Score])> {
+        vec![("mobility_mg", &mut self.mg), ("mobility_eg", &mut self.eg)]
+    }
+}
+
+/// Piece mobility and the attacks it makes on the kings.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Mobility {
+    /// Midgame and endgame mobility of White and of Black.
+    pub score: [(Score, Score); 2],
+    /// Attacks of White on the black king and of Black on the white king.
+    pub king_attacks: [KingAttacks; 2],
+}
+
+fn kind(piece: &PieceType) -> Option<usize> {
+    match piece {
+        PieceType::Knight(_, _) => Some(0),
+        PieceType::Bishop(_, _) => Some(1),
+        PieceType::Rook(_, _, _) => Some(2),
+        PieceType::Queen(_, _) => Some(3),
+        _ => None,
+    }
+}
+
+/// Scores the moves available to the knights, bishops, rooks and queens of `board` and counts
+/// their attacks on the enemy king's zone.
+pub fn evaluate(board: &dyn BoardTrait, weights: &MobilityWeights) -> Mobility {
+    let mut mobility = Mobility::default();
+    for (color, index) in [(Color::White, 0), (Color::Black, 1)] {
+        let zone = king_safety::king(board, color.opponent())
+            .map(|king| king_safety::king_zone(&king))
+            .unwrap_or_default();
+        let pieces = match color {
+            Color::White => board.get_all_white_pieces(),
+            Color::Black => board.get_all_black_pieces(),
+        };
+
+        for piece in pieces {
+            let Some(kind) = kind(piece) else {
+                continue;
+            };
+            let targets: Vec<_> = piece
+                .possible_moves(board)
+                .into_iter()
+                .map(|m| m.to)
+                .collect();
+            let moves = targets.len() as Score - MOBILITY_BASE[kind];
+            mobility.score[index].0 += moves * weights.mg[kind];
+            mobility.score[index].1 += moves * weights.eg[kind];
+            mobility.king_attacks[index].add(piece, &targets, &zone);
+        }
+    }
+    mobility
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{board, fen::Fen};
+
+    fn mobility(fen: &str) -> Mobility {
+        let fen: Fen = fen.parse().unwrap();
+        evaluate(fen.board.as_ref(), &MobilityWeights::default())
+    }
+
+    #[test]
+    fn test_start_position_is_balanced() {
+        let mobility = evaluate(&board::new_board(), &MobilityWeights::default());
+        assert_eq!(mobility.score[0], mobility.score[1]);
+        assert_eq!(mobility.king_attacks, [KingAttacks::default(); 2]);
+    }
+
+    #[test]
+    fn test_active_pieces_score_higher() {
+        let centre = mobility("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+        let corner = mobility("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+        assert_eq!(
+            centre.score[0].0,
+            (8 - 4) * MobilityWeights::default().mg[0]
+        );
+        assert_eq!(
+            corner.score[0].0,
+            (2 - 4) * MobilityWeights::default().mg[0]
+        );
+
+        let bishop = mobility("4k3/8/8/8/8/8/1P6/2B1K3 w - - 0 1");
+        let developed = mobility("4k3/8/8/8/8/3B4/1P6/4K3 w - - 0 1");
+        assert!(developed.score[0] > bishop.score[0]);
+    }
+
+    #[test]
+    fn test_king_attacks() {
+        let attacked = mobility("6k1/5ppp/8/3Q2N1/8/8/8/6K1 w - - 0 1");
+        assert_eq!(attacked.king_attacks[0].count(), 2, "Qd5 and Ng5 hit f7");
+        assert_eq!(attacked.king_attacks[1], KingAttacks::default());
+
+        let distant = mobility("6k1/5ppp/8/8/8/8/8/3QN1K1 w - - 0 1");
+        assert_eq!(distant.king_attacks[0].count(), 0);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    Move,
+    ai::{MAX_DEPTH, Score},
+    board::{BOARD_SQUARES, BoardTrait},
+    pieces::{Color, PieceType, Promotion},
+};
+
+const HASH_MOVE: Score = 1_000_000;
+const CAPTURE: Score = 100_000;
+const KILLERS: [Score; 2] = [90_000, 89_000];
+/// History scores are halved once one of them reaches this, keeping them below the killers.
+const HISTORY_LIMIT: Score = 80_000;
+const SQUARES: usize = BOARD_SQUARES as usize;
+const QUEEN_VALUE: Score = 900;
+
+/// Orders moves so alpha-beta finds cutoffs early: the hash move, then captures and queen
+/// promotions by most valuable victim and least valuable attacker, then the killer moves of
+/// the ply and finally quiet moves by their history score.
+#[derive(Debug, Clone)]
+pub struct MoveOrdering {
+    killers: Vec<[Option<Move>; 2]>,
+    history: Vec<Score>,
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        MoveOrdering {
+            killers: vec![[None; 2]; MAX_DEPTH as usize * 2],
+            history: vec![0; 2 * SQUARES * SQUARES],
+        }
+    }
+}
+
+impl MoveOrdering {
+    pub fn clear(&mut self) {
+        *self = MoveOrdering::default();
+    }
+
+    /// Prepares for a new search: killers only make sense within one tree, history is kept
+    /// at half weight.
+    pub fn new_search(&mut self) {
+        self.killers.fill([None; 2]);
+        self.history.iter_mut().for_each(|score| *score /= 2);
+    }
+
+    /// Sorts `moves` of `color` at `ply` from most to least promising.
+    pub fn order(
+        &self,
+        board: &dyn BoardTrait,
+        color: Color,
+        moves: &mut [Move],
+        hash_move: Option<Move>,
+        ply: u8,
+    ) {
+        moves.sort_by_cached_key(|m| -self.score(board, color, m, hash_move, ply));
+    }
+
+    fn score(
+        &self,
+        board: &dyn BoardTrait,
+        color: Color,
+        m: &Move,
+        hash_move: Option<Move>,
+        ply: u8,
+    ) -> Score {
+        if hash_move == Some(*m) {
+            return HASH_MOVE;
+        }
+        if let Some(score) = mvv_lva(board, m) {
+            return CAPTURE + score;
+        }
+        if let Some(slot) = self
+            .killers
+            .get(ply as usize)
+            .and_then(|killers| killers.iter().position(|killer| *killer == Some(*m)))
+        {
+            return KILLERS[slot];
+        }
+        self.history[history_index(color, m)]
+    }
+
+    /// Remembers a quiet move that caused a beta cutoff at `ply` after a search of `depth`.
+    pub fn record_cutoff(
+        &mut self,
+        board: &dyn BoardTrait,
+        color: Color,
+        m: &Move,
+        depth: u8,
+        ply: u8,
+    ) {
+        if mvv_lva(board, m).is_some() {
+            return;
+        }
+
+        if let Some(killers) = self.killers.get_mut(ply as usize)
+            && killers[0] != Some(*m)
+        {
+            killers[1] = killers[0];
+            killers[0] = Some(*m);
+        }
+
+        let index = history_index(color, m);
+        self.history[index] += depth as Score * depth as Score;
+        if self.history[index] >= HISTORY_LIMIT {
+            self.history.iter_mut().for_each(|score| *score /= 2);
+        }
+    }
+}
+
+fn history_index(color: Color, m: &Move) -> usize {
+    let color = match color {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    (color * SQUARES + m.from.to_index() as usize) * SQUARES + m.to.to_index() as usize
+}
+
+/// Returns the value in centipawns of the piece `m` captures, if it captures anything.
+pub fn capture_value(board: &dyn BoardTrait, m: &Move) -> Option<Score> {
+    match (board.get_piece(m.from), board.get_piece(m.to)) {
+        (_, Some(victim)) => Some(victim.value()),
+        (Some(PieceType::Pawn(_, _, _)), None) if m.from.x != m.to.x => Some(100),
+        _ => None,
+    }
+}
+
+/// Scores a capture or queen promotion, preferring the most valuable victim and then the least
+/// valuable attacker. Quiet moves and under-promotions return `None`.
+fn mvv_lva(board: &dyn BoardTrait, m: &Move) -> Option<Score> {
+    let victim = match (capture_value(board, m), m.promotion) {
+        (Some(victim), _) => victim,
+        (None, Some(Promotion::Queen)) => 0,
+        (None, _) => return None,
+    };
+    let promotion = match m.promotion {
+        Some(Promotion::Queen) => QUEEN_VALUE,
+        _ => 0,
+    };
+    // Attackers are ranked rather than valued, so the king comes last instead of first.
+    let attacker = match board.get_piece(m.from) {
+        Some(PieceType::King(_, _, _)) => QUEEN_VALUE + 1,
+        Some(attacker) => attacker.value(),
+        None => 0,
+    };
+    Some((victim + promotion) * 8 - attacker / 100)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fen::Fen;
+
+    fn moves(list: &[(&str, &str)]) -> Vec<Move> {
+        list.iter()
+            .map(|(from, to)| Move::new(from.parse().unwrap(), to.parse().unwrap()))
+            .collect()
+    }
+
+    fn board() -> Box<dyn BoardTrait> {
+        let fen: Fen = "4k3/8/2q1r3/1P6/8/7Q/8/2R1K3 w - - 0 1".parse().unwrap();
+        fen.board
+    }
+
+    #[test]
+    fn test_captures_by_mvv_lva() {
+        let board = board();
+        let mut list = moves(&[("e1", "d1"), ("h3", "e6"), ("c1", "c6"), ("b5", "c6")]);
+        MoveOrdering::default().order(board.as_ref(), Color::White, &mut list, None, 0);
+        assert_eq!(
+            list,
+            moves(&[("b5", "c6"), ("c1", "c6"), ("h3", "e6"), ("e1", "d1")])
+        );
+    }
+
+    #[test]
+    fn test_hash_move_killers_and_history() {
+        let board = board();
+        let board = board.as_ref();
+        let quiet = moves(&[("e1", "d1"), ("e1", "f1"), ("h3", "h4"), ("c1", "c2")]);
+        let capture = moves(&[("c1", "c6")])[0];
+
+        let mut ordering = MoveOrdering::default();
+        ordering.record_cutoff(board, Color::White, &quiet[0], 3, 2);
+        ordering.record_cutoff(board, Color::White, &quiet[1], 3, 2);
+        ordering.record_cutoff(board, Color::White, &capture, 3, 2);
+        ordering.record_cutoff(board, Color::White, &quiet[2], 4, 5);
+
+        let mut list = vec![quiet[3], quiet[0], quiet[1], capture];
+        ordering.order(board, Color::White, &mut list, Some(quiet[3]), 2);
+        assert_eq!(list, vec![quiet[3], capture, quiet[1], quiet[0]]);
+
+        let mut list = vec![quiet[0], quiet[2]];
+        ordering.order(board, Color::White, &mut list, None, 3);
+        assert_eq!(
+            list,
+            vec![quiet[2], quiet[0]],
+            "Deeper cutoffs weigh more in the history"
+        );
+
+        ordering.new_search();
+        let mut list = vec![quiet[3], quiet[0]];
+        ordering.order(board, Color::White, &mut list, None, 2);
+        assert_eq!(list, vec![quiet[0], quiet[3]], "Only history is left");
+        ordering.clear();
+        let mut list = vec![quiet[3], quiet[0]];
+        ordering.order(board, Color::White, &mut list, None, 2);
+        assert_eq!(list, vec![quiet[3], quiet[0]]);
+    }
+}
@@ -0,0 +1,393 @@
+use crate::{
+    Position,
+    ai::Score,
+    board::BoardTrait,
+    pieces::{Color, Piece, PieceType},
+};
+
+const PAWN_TABLE_ENTRIES: usize = 1 << 14;
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+/// Weights of the pawn structure terms. Single terms hold a midgame and an endgame value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PawnWeights {
+    /// Bonus of a passed pawn by its rank, counted from its own side.
+    pub passed_mg: [Score; 8],
+    pub passed_eg: [Score; 8],
+    /// Bonus of a pawn defended by a pawn or standing next to one, by its rank.
+    pub connected_mg: [Score; 8],
+    pub connected_eg: [Score; 8],
+    pub isolated: [Score; 2],
+    pub doubled: [Score; 2],
+    pub backward: [Score; 2],
+    /// Penalty for every group of pawns on neighbouring files after the first.
+    pub island: [Score; 2],
+}
+
+impl Default for PawnWeights {
+    fn default() -> Self {
+        PawnWeights {
+            passed_mg: [0, 0, 5, 10, 20, 35, 60, 0],
+            passed_eg: [0, 5, 10, 20, 40, 70, 110, 0],
+            connected_mg: [0, 0, 5, 7, 10, 18, 30, 0],
+            connected_eg: [0, 0, 3, 5, 10, 20, 35, 0],
+            isolated: [-10, -15],
+            doubled: [-10, -25],
+            backward: [-8, -12],
+            island: [-5, -10],
+        }
+    }
+}
+
+impl PawnWeights {
+    /// Returns every weight by name, see `eval::Weights::groups_mut`.
+    pub fn groups_mut(&mut self) -> Vec<(&'static str, &mut [Score])> {
+        vec![
+            ("passed_mg", &mut self.passed_mg),
+            ("passed_eg", &mut self.passed_eg),
+            ("connected_mg", &mut self.connected_mg),
+            ("connected_eg", &mut self.connected_eg),
+            ("isolated", &mut self.isolated),
+            ("doubled", &mut self.doubled),
+            ("backward", &mut self.backward),
+            ("island", &mut self.island),
+        ]
+    }
+}
+
+/// The pawn-only part of the evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PawnStructure {
+    /// Midgame and endgame score of the pawns of White and of Black, each for its own side.
+    pub score: [(Score, Score); 2],
+    /// Passed pawns of White and Black as bitboards, `a1` being the lowest bit. Their bonus
+    /// depends on the pieces blocking them, so it is left out of `score`.
+    pub passed: [u64; 2],
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    key: u64,
+    structure: PawnStructure,
+}
+
+/// A hash table of pawn structures indexed by `BoardTrait::pawn_key`. Pawns move rarely, so
+/// most positions of a search share a handful of structures. The weights must not change while
+/// the table is in use.
+#[derive(Debug, Clone)]
+pub struct PawnTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        PawnTable {
+            entries: vec![None; PAWN_TABLE_ENTRIES],
+        }
+    }
+}
+
+impl PawnTable {
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Returns the pawn structure of `board`, evaluating it only if it is not cached.
+    pub fn get(&mut self, board: &dyn BoardTrait, weights: &PawnWeights) -> PawnStructure {
+        let key = board.pawn_key();
+        let slot = &mut self.entries[key as usize & (PAWN_TABLE_ENTRIES - 1)];
+        if let Some(entry) = slot
+            && entry.key == key
+        {
+            return entry.structure;
+        }
+        let structure = pawn_structure(board, weights);
+        *slot = Some(Entry { key, structure });
+        structure
+    }
+}
+
+fn color_index(color: &Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn square(position: &Position) -> u32 {
+    position.to_index() as u32
+}
+
+fn file_mask(file: u32) -> u64 {
+    FILE_A << file
+}
+
+fn adjacent_files(file: u32) -> u64 {
+    let left = if file > 0 { file_mask(file - 1) } else { 0 };
+    let right = if file < 7 { file_mask(file + 1) } else { 0 };
+    left | right
+}
+
+/// Returns the ranks in front of `rank` as seen by `color`.
+fn ranks_ahead(color: usize, rank: u32) -> u64 {
+    match color {
+        0 => u64::MAX.checked_shl(8 * (rank + 1)).unwrap_or(0),
+        _ => (1 << (8 * rank)) - 1,
+    }
+}
+
+/// Returns the squares attacked by `pawns` of `color`.
+fn pawn_attacks(color: usize, pawns: u64) -> u64 {
+    match color {
+        0 => ((pawns & !FILE_A) << 7) | ((pawns & !FILE_H) << 9),
+        _ => ((pawns & !FILE_A) >> 9) | ((pawns & !FILE_H) >> 7),
+    }
+}
+
+/// Returns the square in front of `square` for `color`.
+fn stop_square(color: usize, square: u32) -> u32 {
+    match color {
+        0 => square + 8,
+        _ => square - 8,
+    }
+}
+
+fn relative_rank(color: usize, rank: u32) -> usize {
+    match color {
+        0 => rank as usize,
+        _ => 7 - rank as usize,
+    }
+}
+
+/// Returns the pawns of both colors as bitboards.
+fn pawn_bitboards(board: &dyn BoardTrait) -> [u64; 2] {
+    let mut pawns = [0; 2];
+    for piece in board
+        .get_all_white_pieces()
+        .into_iter()
+        .chain(board.get_all_black_pieces())
+    {
+        if let PieceType::Pawn(color, position, _) = piece {
+            pawns[color_index(color)] |= 1 << square(position);
+        }
+    }
+    pawns
+}
+
+/// Evaluates isolated, doubled, backward and connected pawns and pawn islands of `board`, and
+/// finds its passed pawns.
+pub fn pawn_structure(board: &dyn BoardTrait, weights: &PawnWeights) -> PawnStructure {
+    let pawns = pawn_bitboards(board);
+    let mut structure = PawnStructure {
+        score: [(0, 0); 2],
+        passed: [0; 2],
+    };
+
+    for color in 0..2 {
+        let own = pawns[color];
+        let enemy = pawns[1 - color];
+        let own_attacks = pawn_attacks(color, own);
+        let enemy_attacks = pawn_attacks(1 - color, enemy);
+        let mut mg = 0;
+        let mut eg = 0;
+
+        let mut remaining = own;
+        while remaining != 0 {
+            let square = remaining.trailing_zeros();
+            remaining &= remaining - 1;
+            let file = square % 8;
+            let rank = square / 8;
+            let ahead = ranks_ahead(color, rank);
+            let neighbours = own & adjacent_files(file);
+
+            let doubled = own & file_mask(file) & ahead != 0;
+            if doubled {
+                mg += weights.doubled[0];
+                eg += weights.doubled[1];
+            } else if enemy & (file_mask(file) | adjacent_files(file)) & ahead == 0 {
+                structure.passed[color] |= 1 << square;
+            }
+
+            if neighbours == 0 {
+                mg += weights.isolated[0];
+                eg += weights.isolated[1];
+            } else if neighbours & !ahead == 0
+                && enemy_attacks & (1 << stop_square(color, square)) != 0
+            {
+                mg += weights.backward[0];
+                eg += weights.backward[1];
+            }
+
+            let phalanx = neighbours & (0xFF << (8 * rank)) != 0;
+            if phalanx || own_attacks & (1 << square) != 0 {
+                let rank = relative_rank(color, rank);
+                mg += weights.connected_mg[rank];
+                eg += weights.connected_eg[rank];
+            }
+        }
+
+        let files = (0..8).filter(|file| own & file_mask(*file) != 0);
+        let occupied = files.fold(0u8, |occupied, file| occupied | 1 << file);
+        let islands = (occupied & !(occupied << 1)).count_ones() as Score;
+        if islands > 1 {
+            mg += (islands - 1) * weights.island[0];
+            eg += (islands - 1) * weights.island[1];
+        }
+
+        structure.score[color] = (mg, eg);
+    }
+    structure
+}
+
+/// Scores the passed pawns of `structure` on `board` for White and for Black. A passed pawn gets
+/// a bonus growing with its rank, halved when an enemy piece blocks its way.
+pub fn passed_pawns(
+    board: &dyn BoardTrait,
+    structure: &PawnStructure,
+    weights: &PawnWeights,
+) -> [(Score, Score); 2] {
+    let mut score = [(0, 0); 2];
+    for (color, score) in score.iter_mut().enumerate() {
+        let mut passed = structure.passed[color];
+        while passed != 0 {
+            let square = passed.trailing_zeros();
+            passed &= passed - 1;
+            let rank = relative_rank(color, square / 8);
+            let mut bonus = (weights.passed_mg[rank], weights.passed_eg[rank]);
+
+            let stop = stop_square(color, square);
+            let stop = Position::new((b'a' + (stop % 8) as u8) as char, (stop / 8 + 1) as i8);
+            if let Some(blocker) = board.get_piece(stop)
+                && color_index(blocker.color()) != color
+            {
+                bonus = (bonus.0 / 2, bonus.1 / 2);
+            }
+            score.0 += bonus.0;
+            score.1 += bonus.1;
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{board, fen::Fen};
+
+    fn structure(fen: &str) -> PawnStructure {
+        let fen: Fen = fen.parse().unwrap();
+        pawn_structure(fen.board.as_ref(), &PawnWeights::default())
+    }
+
+    fn bit(position: &str) -> u64 {
+        1 << square(&position.parse().unwrap())
+    }
+
+    #[test]
+    fn test_start_position_is_balanced() {
+        let board = board::new_board();
+        let w = PawnWeights::default();
+        let structure = pawn_structure(&board, &w);
+        assert_eq!(structure.passed, [0, 0]);
+        assert_eq!(structure.score[0], structure.score[1]);
+        assert_eq!(passed_pawns(&board, &structure, &w), [(0, 0); 2]);
+    }
+
+    #[test]
+    fn test_passed_pawns() {
+        let passed = structure("4k3/8/1p6/8/3P4/8/P7/4K3 w - - 0 1").passed;
+        assert_eq!(passed[0], bit("d4"), "b6 stops the a-pawn");
+        assert_eq!(passed[1], 0, "a2 guards b6's path");
+
+        let structure = structure("4k3/8/8/8/8/3P4/3P4/4K3 w - - 0 1");
+        assert_eq!(
+            structure.passed[0],
+            bit("d3"),
+            "Only the front pawn is passed"
+        );
+    }
+
+    #[test]
+    fn test_passed_pawns_grow_and_are_blockaded() {
+        let w = PawnWeights::default();
+        let score = |fen: &str| {
+            let fen: Fen = fen.parse().unwrap();
+            let board = fen.board.as_ref();
+            passed_pawns(board, &pawn_structure(board, &w), &w)[0]
+        };
+        let far = score("4k3/8/8/8/8/3P4/8/4K3 w - - 0 1");
+        let near = score("4k3/8/3P4/8/8/8/8/4K3 w - - 0 1");
+        assert!(near.1 > far.1);
+
+        let blocked = score("4k3/3n4/3P4/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(blocked, (w.passed_mg[5] / 2, w.passed_eg[5] / 2));
+        let own_blocker = score("4k3/3N4/3P4/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(own_blocker, near);
+    }
+
+    #[test]
+    fn test_weaknesses() {
+        let w = PawnWeights::default();
+        let isolated = structure("4k3/8/8/8/8/8/P1P5/4K3 w - - 0 1");
+        assert_eq!(
+            isolated.score[0],
+            (
+                2 * w.isolated[0] + w.island[0],
+                2 * w.isolated[1] + w.island[1]
+            )
+        );
+
+        let doubled = structure("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1");
+        assert_eq!(
+            doubled.score[0],
+            (
+                w.doubled[0] + 2 * w.isolated[0],
+                w.doubled[1] + 2 * w.isolated[1]
+            )
+        );
+
+        let backward = structure("4k3/8/8/p7/2P5/1P6/8/4K3 w - - 0 1");
+        assert_eq!(backward.score[0].0, w.backward[0] + w.connected_mg[3]);
+        assert_eq!(backward.score[1].0, w.isolated[0]);
+        let phalanx = structure("4k3/8/8/p7/1PP5/8/8/4K3 w - - 0 1");
+        assert_eq!(phalanx.score[0].0, 2 * w.connected_mg[3]);
+    }
+
+    #[test]
+    fn test_colors_are_mirrored() {
+        let white = structure("4k3/8/8/8/2P5/1P6/P4PP1/4K3 w - - 0 1");
+        let black = structure("4k3/p4pp1/1p6/2p5/8/8/8/4K3 w - - 0 1");
+        assert_eq!(white.score[0], black.score[1]);
+        assert_eq!(white.passed[0].count_ones(), black.passed[1].count_ones());
+    }
+
+    #[test]
+    fn test_table_caches_by_pawn_key() {
+        let w = PawnWeights::default();
+        let mut table = PawnTable::default();
+        let mut board = board::new_board();
+        let start = table.get(&board, &w);
+        board
+            .make_move(&crate::Move::new(
+                "g1".parse().unwrap(),
+                "f3".parse().unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(
+            table.get(&board, &w),
+            start,
+            "Knight moves keep the pawn key"
+        );
+        board
+            .make_move(&crate::Move::new(
+                "e7".parse().unwrap(),
+                "e5".parse().unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(table.get(&board, &w), pawn_structure(&board, &w));
+        table.clear();
+        assert_eq!(table.get(&board, &w), pawn_structure(&board, &w));
+    }
+}
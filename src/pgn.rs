@@ -0,0 +1,389 @@
+use std::fmt::Display;
+
+use crate::{
+    Game,
+    board::GameStatus,
+    fen::{FenError, START_FEN},
+    pieces::Color,
+    san::{SanError, parse_san},
+};
+
+const LINE_LENGTH: usize = 80;
+
+/// The seven-tag roster plus any other tags read from or written to a PGN file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tags {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+    pub result: String,
+    pub other: Vec<(String, String)>,
+}
+
+impl Default for Tags {
+    fn default() -> Self {
+        Tags {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+            result: "*".to_string(),
+            other: Vec::new(),
+        }
+    }
+}
+
+impl Tags {
+    fn set(&mut self, name: &str, value: String) {
+        match name {
+            "Event" => self.event = value,
+            "Site" => self.site = value,
+            "Date" => self.date = value,
+            "Round" => self.round = value,
+            "White" => self.white = value,
+            "Black" => self.black = value,
+            "Result" => self.result = value,
+            _ => self.other.push((name.to_string(), value)),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.other
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Returns the PGN result of `game`, falling back to `*` while it is still going on.
+pub fn result(game: &Game) -> &'static str {
+    match game.board.status(&game.turn) {
+        GameStatus::Checkmate => match game.turn {
+            Color::White => "0-1",
+            Color::Black => "1-0",
+        },
+        GameStatus::Stalemate => "1/2-1/2",
+        GameStatus::Check | GameStatus::Ongoing => {
+            if game.draw_reason().is_some() {
+                "1/2-1/2"
+            } else {
+                "*"
+            }
+        }
+    }
+}
+
+/// Writes `game` as PGN. The result tag is taken from the game when it has ended on the board
+/// and from `tags` otherwise, so resignations and agreed draws can still be recorded.
+pub fn write(game: &Game, tags: &Tags) -> String {
+    let result = match result(game) {
+        "*" => tags.result.as_str(),
+        result => result,
+    };
+
+    let mut pgn = String::new();
+    for (name, value) in [
+        ("Event", &tags.event),
+        ("Site", &tags.site),
+        ("Date", &tags.date),
+        ("Round", &tags.round),
+        ("White", &tags.white),
+        ("Black", &tags.black),
+    ] {
+        pgn.push_str(&tag(name, value));
+    }
+    pgn.push_str(&tag("Result", result));
+    if game.initial_fen != START_FEN {
+        pgn.push_str(&tag("SetUp", "1"));
+        pgn.push_str(&tag("FEN", &game.initial_fen));
+    }
+    for (name, value) in tags
+        .other
+        .iter()
+        .filter(|(name, _)| name != "SetUp" && name != "FEN")
+    {
+        pgn.push_str(&tag(name, value));
+    }
+    pgn.push('\n');
+
+    let scoresheet = game.scoresheet();
+    let mut tokens: Vec<&str> = scoresheet.split_whitespace().collect();
+    tokens.push(result);
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(token);
+    }
+    pgn.push_str(&line);
+    pgn.push('\n');
+
+    pgn
+}
+
+fn tag(name: &str, value: &str) -> String {
+    format!(
+        "[{} \"{}\"]\n",
+        name,
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// Reads the first game of a PGN file, replaying every move through the rules.
+pub fn read(pgn: &str) -> Result<(Tags, Game), PgnError> {
+    let mut tags = Tags::default();
+    let mut movetext = String::new();
+    let mut lines = pgn.lines().map(str::trim).peekable();
+
+    while let Some(line) = lines.next_if(|line| line.is_empty() || line.starts_with('[')) {
+        if line.is_empty() {
+            continue;
+        }
+        let (name, value) = read_tag(line)?;
+        tags.set(&name, value);
+    }
+    for line in lines {
+        if line.starts_with('[') {
+            break;
+        }
+        if line.starts_with('%') {
+            continue;
+        }
+        movetext.push_str(line);
+        movetext.push('\n');
+    }
+
+    let mut game = match tags.get("FEN") {
+        Some(fen) => Game::from_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => Game::new(),
+    };
+
+    for (index, token) in movetext_tokens(&movetext)?.into_iter().enumerate() {
+        let ply = index + 1;
+        let m = parse_san(game.board.as_ref(), &game.turn, &token).map_err(|error| {
+            PgnError::IllegalMove {
+                ply,
+                san: token.clone(),
+                error,
+            }
+        })?;
+        game.make_move(&m).map_err(|_| PgnError::IllegalMove {
+            ply,
+            san: token.clone(),
+            error: SanError::IllegalMove(token.clone()),
+        })?;
+    }
+
+    Ok((tags, game))
+}
+
+fn read_tag(line: &str) -> Result<(String, String), PgnError> {
+    let invalid = || PgnError::InvalidTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, value) = inner
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    Ok((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+/// Splits movetext into SAN moves, dropping move numbers, comments, variations, NAGs and the
+/// game termination marker.
+fn movetext_tokens(movetext: &str) -> Result<Vec<String>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0;
+
+    while let Some(c) = chars.next() {
+        if !c.is_whitespace() && !matches!(c, '{' | ';' | '(' | ')') {
+            token.push(c);
+            continue;
+        }
+
+        if variation_depth == 0 {
+            push_token(&mut tokens, &token);
+        }
+        token.clear();
+        match c {
+            '{' if !chars.any(|c| c == '}') => return Err(PgnError::UnterminatedComment),
+            ';' => {
+                let _ = chars.any(|c| c == '\n');
+            }
+            '(' => variation_depth += 1,
+            ')' => {
+                if variation_depth == 0 {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                variation_depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    if variation_depth != 0 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+    push_token(&mut tokens, &token);
+
+    Ok(tokens)
+}
+
+fn push_token(tokens: &mut Vec<String>, token: &str) {
+    if token.is_empty() || token.starts_with('$') {
+        return;
+    }
+    if matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        return;
+    }
+
+    // Move numbers may be glued to the move, as in `1.e4` or `3...Nf6`.
+    let digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    let token = match digits.strip_prefix('.') {
+        Some(rest) => rest.trim_start_matches('.'),
+        None => token,
+    };
+    if !token.is_empty() {
+        tokens.push(token.to_string());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnError {
+    InvalidTag(String),
+    InvalidFen(FenError),
+    UnterminatedComment,
+    UnbalancedVariation,
+    IllegalMove {
+        ply: usize,
+        san: String,
+        error: SanError,
+    },
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::InvalidTag(line) => write!(f, "'{}' is not a valid tag pair", line),
+            PgnError::InvalidFen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnError::UnterminatedComment => write!(f, "comment is missing its closing brace"),
+            PgnError::UnbalancedVariation => write!(f, "variation parentheses do not match"),
+            PgnError::IllegalMove { ply, san, error } => {
+                write!(f, "ply {} ({}): {}", ply, san, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Move, Position};
+
+    const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8.
+Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14.
+Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+    #[test]
+    fn test_read_and_write_opera_game() {
+        let (tags, game) = read(OPERA_GAME).unwrap();
+        assert_eq!(tags.white, "Paul Morphy");
+        assert_eq!(game.moves().len(), 33);
+        assert_eq!(result(&game), "1-0");
+        assert_eq!(write(&game, &tags), OPERA_GAME);
+    }
+
+    #[test]
+    fn test_read_skips_comments_variations_and_nags() {
+        let pgn = "1.e4 $1 e5 (1...c5 2.Nf3 {Sicilian} (2.c3)) 2. Nf3 ; a comment\n2... Nc6 *";
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!(
+            game.moves(),
+            vec![
+                Move::new(Position::new('e', 2), Position::new('e', 4)),
+                Move::new(Position::new('e', 7), Position::new('e', 5)),
+                Move::new(Position::new('g', 1), Position::new('f', 3)),
+                Move::new(Position::new('b', 8), Position::new('c', 6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_reports_illegal_ply() {
+        let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bb5 Ke6 *";
+        assert_eq!(
+            Game::from_pgn(pgn).err(),
+            Some(PgnError::IllegalMove {
+                ply: 6,
+                san: "Ke6".to_string(),
+                error: SanError::IllegalMove("Ke6".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_from_fen_position() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30").unwrap();
+        game.make_move(&Move::new(Position::new('e', 8), Position::new('d', 7)))
+            .unwrap();
+        game.make_move(&Move::new(Position::new('e', 2), Position::new('e', 4)))
+            .unwrap();
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n"));
+        assert!(pgn.ends_with("\n30... Kd7 31. e4 *\n"), "{}", pgn);
+
+        let replayed = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(replayed.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn test_malformed_pgn() {
+        assert_eq!(
+            Game::from_pgn("[Event Paris]\n1. e4 *").err(),
+            Some(PgnError::InvalidTag("[Event Paris]".to_string()))
+        );
+        assert_eq!(
+            Game::from_pgn("1. e4 {unterminated").err(),
+            Some(PgnError::UnterminatedComment)
+        );
+        assert_eq!(
+            Game::from_pgn("1. e4 (1. d4 *").err(),
+            Some(PgnError::UnbalancedVariation)
+        );
+    }
+}
@@ -1,12 +1,8 @@
-use std::ops::ControlFlow;
+use crate::{Position, board::BoardTrait, pieces::Color};
 
-use crate::{
-    Position,
-    board::{BOARD_SQUARES, BoardTrait},
-    pieces::Color,
-};
+use super::{ChessError, Piece, PieceType, sliding_move, sliding_moves};
 
-use super::{ChessError, Piece, PieceType};
+pub const DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
 
 pub fn move_to(
     bishop: &PieceType,
@@ -15,17 +11,15 @@ pub fn move_to(
 ) -> Result<Option<PieceType>, ChessError> {
     match bishop {
         PieceType::Bishop(color, current_position) => {
-            can_move_to(&current_position, &color, position, board)?;
+            can_move_to(current_position, color, position, board)?;
 
             let captured_piece = board.square(&position).piece;
-            board.square_mut(&current_position).piece = None;
+            board.square_mut(current_position).piece = None;
             board.square_mut(&position).piece = Some(PieceType::Bishop(*color, position));
 
             Ok(captured_piece)
         }
-        _ => {
-            return Err(ChessError::InvalidPiece);
-        }
+        _ => Err(ChessError::InvalidPiece),
     }
 }
 
@@ -35,74 +29,13 @@ pub fn can_move_to(
     position: Position,
     board: &dyn BoardTrait,
 ) -> Result<(), ChessError> {
-    let new_index = position.to_index();
-    let old_index = current_position.to_index();
-
-    let jump = new_index - old_index;
-    if jump % 7 != 0 && jump % 9 != 0 {
-        return Err(ChessError::InvalidMove);
-    }
-
-    bishop_move(board, old_index, new_index, jump)?;
+    sliding_move(board, current_position, &position, &DIRECTIONS)?;
 
     let square = board.square(&position);
-    if let Some(piece) = &square.piece {
-        if piece.color() == color {
-            return Err(ChessError::InvalidCapture);
-        }
-    }
-
-    Ok(())
-}
-
-pub fn bishop_move(
-    board: &dyn BoardTrait,
-    old_index: i32,
-    new_index: i32,
-    jump: i32,
-) -> Result<(), ChessError> {
-    if jump % 7 == 0 {
-        let mut index = old_index;
-        if new_index > old_index {
-            index += 7;
-            while index != new_index {
-                let square = board.square(&Position::from_index(index));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index += 7;
-            }
-        } else {
-            index -= 7;
-            while index != new_index {
-                let square = board.square(&Position::from_index(index.abs()));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index -= 7;
-            }
-        }
-    } else {
-        let mut index = old_index;
-        if new_index > old_index {
-            index += 9;
-            while index != new_index {
-                let square = board.square(&Position::from_index(index));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index += 9;
-            }
-        } else {
-            index -= 9;
-            while index != new_index {
-                let square = board.square(&Position::from_index(index.abs()));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index -= 9;
-            }
-        }
+    if let Some(piece) = &square.piece
+        && piece.color() == color
+    {
+        return Err(ChessError::InvalidCapture);
     }
 
     Ok(())
@@ -113,65 +46,15 @@ pub fn possible_moves(
     color: &Color,
     board: &dyn BoardTrait,
 ) -> Vec<Position> {
-    let mut positions = vec![];
-
-    let current_index = current_position.to_index();
-    let mut next_inndex = current_index + 7;
-    while next_inndex <= BOARD_SQUARES {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex += 7;
-    }
-
-    let mut next_inndex = current_index + 9;
-    while next_inndex <= BOARD_SQUARES {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex += 9;
-    }
-
-    let mut next_inndex = current_index - 7;
-    while next_inndex >= 0 {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex -= 7;
-    }
-
-    let mut next_inndex = current_index - 9;
-    while next_inndex >= 0 {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex -= 9;
-    }
-    positions
-}
-
-fn valide_move(
-    color: &Color,
-    board: &dyn BoardTrait,
-    next_inndex: i32,
-    positions: &mut Vec<Position>,
-) -> ControlFlow<()> {
-    let square = board.square(&Position::from_index(next_inndex));
-    if square.piece.is_some() {
-        if square.piece.as_ref().unwrap().color() != color {
-            positions.push(Position::new(square.x, square.y));
-        }
-        return ControlFlow::Break(());
-    }
-    positions.push(Position::new(square.x, square.y));
-    ControlFlow::Continue(())
+    sliding_moves(current_position, color, &DIRECTIONS, board)
 }
 
 #[cfg(test)]
 mod test {
 
     use crate::{
-        BoardTrait, Position, board,
+        BoardTrait, Position,
+        board::{self, BOARD_SQUARES},
         pieces::{ChessError, Color, Piece, PieceType, bishop::possible_moves},
     };
 
@@ -300,4 +183,41 @@ mod test {
             "d4 Black Bishop should have 8 possible moves"
         );
     }
+
+    #[test]
+    fn test_bishop_moves_from_edge_squares() {
+        init();
+        let board = board::empty_board();
+        for index in 0..BOARD_SQUARES {
+            let position = Position::from_index(index);
+            if position.file() % 7 != 0 && position.rank() % 7 != 0 {
+                continue;
+            }
+            let moves = possible_moves(&position, &Color::White, &board);
+            for to in moves.iter() {
+                assert_eq!(
+                    (to.file() - position.file()).abs(),
+                    (to.rank() - position.rank()).abs(),
+                    "{} Bishop should not wrap around the board to {}",
+                    position,
+                    to
+                );
+            }
+            assert_eq!(moves.len(), 7, "{} Bishop should have 7 moves", position);
+        }
+    }
+
+    #[test]
+    fn test_bishop_does_not_wrap_from_h4_to_a6() {
+        init();
+        let mut board = board::empty_board();
+        let mut bishop = PieceType::Bishop(Color::White, Position::new('h', 4));
+        board.square_mut(&Position::new('h', 4)).piece = Some(bishop);
+        let result = bishop.move_to(Position::new('a', 6), &mut board);
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::InvalidMove,
+            "h4 Bishop should not be able to move to a6"
+        );
+    }
 }
@@ -2,186 +2,171 @@ use crate::{BoardTrait, Position, pieces::ChessError};
 
 use super::{Color, Piece, PieceType};
 
+pub const OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
 pub fn move_to(
     king: &PieceType,
     position: Position,
     board: &mut dyn BoardTrait,
 ) -> Result<Option<PieceType>, ChessError> {
     match king {
-        PieceType::King(color, current_position) => {
-            can_move_to(&current_position, &color, position, board)?;
+        PieceType::King(color, current_position, is_first_move) => {
+            can_move_to(current_position, color, *is_first_move, position, board)?;
+
+            if is_castling(current_position, &position) {
+                let (rook_from, rook_to) = castling_rook_positions(&position);
+                board.square_mut(&rook_from).piece = None;
+                board.square_mut(&rook_to).piece = Some(PieceType::Rook(*color, rook_to, false));
+            }
 
             let captured_piece = board.square(&position).piece;
-            board.square_mut(&current_position).piece = None;
-            board.square_mut(&position).piece = Some(PieceType::King(*color, position));
+            board.square_mut(current_position).piece = None;
+            board.square_mut(&position).piece = Some(PieceType::King(*color, position, false));
 
             Ok(captured_piece)
         }
-        _ => {
-            return Err(ChessError::InvalidPiece);
-        }
+        _ => Err(ChessError::InvalidPiece),
     }
 }
 
 pub fn can_move_to(
     current_position: &Position,
     color: &Color,
+    is_first_move: bool,
     position: Position,
     board: &dyn BoardTrait,
 ) -> Result<(), ChessError> {
-    let new_index = position.to_index();
-    let old_index = current_position.to_index();
+    if is_castling(current_position, &position) {
+        return can_castle(current_position, color, is_first_move, position, board);
+    }
 
-    let jump = (new_index - old_index).abs();
-    if jump != 7 && jump != 8 && jump != 9 && jump != 1 {
-        //TODO:: King castle
+    if current_position.distance(&position) != 1 {
         return Err(ChessError::InvalidMove);
     }
 
-    let other_pieces = match color {
-        Color::Black => board.get_all_white_pieces(),
-        Color::White => board.get_all_black_pieces(),
+    if is_attacked(&position, color, board) {
+        return Err(ChessError::UnSafeKing);
+    }
+
+    if let Some(piece) = &board.square(&position).piece
+        && piece.color() == color
+    {
+        return Err(ChessError::InvalidCapture);
+    }
+
+    Ok(())
+}
+
+pub fn is_castling(current_position: &Position, position: &Position) -> bool {
+    current_position.x == 'e'
+        && current_position.y == position.y
+        && (position.x == 'g' || position.x == 'c')
+}
+
+/// Returns the rook's source and destination squares for a castling king landing on `position`.
+pub fn castling_rook_positions(position: &Position) -> (Position, Position) {
+    match position.x {
+        'g' => (
+            Position::new('h', position.y),
+            Position::new('f', position.y),
+        ),
+        _ => (
+            Position::new('a', position.y),
+            Position::new('d', position.y),
+        ),
+    }
+}
+
+fn can_castle(
+    current_position: &Position,
+    color: &Color,
+    is_first_move: bool,
+    position: Position,
+    board: &dyn BoardTrait,
+) -> Result<(), ChessError> {
+    let rank = match color {
+        Color::White => 1,
+        Color::Black => 8,
     };
+    if !is_first_move || current_position.y != rank {
+        return Err(ChessError::InvalidMove);
+    }
 
-    for piece in other_pieces {
-        match piece {
-            PieceType::King(_, other_king_position) => {
-                let king_index = other_king_position.to_index();
-                if king_index == new_index + 7
-                    || king_index == new_index - 7
-                    || king_index == new_index + 8
-                    || king_index == new_index - 8
-                    || king_index == new_index + 9
-                    || king_index == new_index - 9
-                    || king_index == new_index + 1
-                    || king_index == new_index - 1
-                {
-                    return Err(ChessError::UnSafeKing);
-                }
-            }
-            PieceType::Pawn(_, pawn_positionn, _) => {
-                let pawn_index = pawn_positionn.to_index();
-                if pawn_index == new_index + 7 || pawn_index == new_index + 9 {
-                    return Err(ChessError::UnSafeKing);
-                }
-            }
-            _ => {
-                if piece.can_move_to(position, board).is_ok() {
-                    return Err(ChessError::UnSafeKing);
-                }
-            }
+    let (rook_position, passing_position) = castling_rook_positions(&position);
+    match board.square(&rook_position).piece {
+        Some(PieceType::Rook(rook_color, _, true)) if rook_color == *color => {}
+        _ => return Err(ChessError::InvalidMove),
+    }
+
+    let between = match position.x {
+        'g' => vec!['f', 'g'],
+        _ => vec!['d', 'c', 'b'],
+    };
+    for x in between {
+        if board.square(&Position::new(x, rank)).piece.is_some() {
+            return Err(ChessError::BlockedMove);
         }
     }
 
-    if let Some(piece) = &board.square(&position).piece {
-        if piece.color() == color {
-            return Err(ChessError::InvalidCapture);
+    // The king may not castle out of, through or into check.
+    for king_position in [*current_position, passing_position, position] {
+        if is_attacked(&king_position, color, board) {
+            return Err(ChessError::UnSafeKing);
         }
     }
 
     Ok(())
 }
 
-pub fn is_check(king: PieceType, board: &dyn BoardTrait) -> bool {
-    match king {
-        PieceType::King(color, position) => {
-            let other_pieces = match color {
-                Color::Black => board.get_all_white_pieces(),
-                Color::White => board.get_all_black_pieces(),
-            };
+/// Returns true if `position` is attacked by any piece of the opponent of `color`.
+pub fn is_attacked(position: &Position, color: &Color, board: &dyn BoardTrait) -> bool {
+    let other_pieces = match color {
+        Color::Black => board.get_all_white_pieces(),
+        Color::White => board.get_all_black_pieces(),
+    };
 
-            let new_index = position.to_index();
-
-            for piece in other_pieces {
-                match piece {
-                    PieceType::King(_, _) => {
-                        // return false;
-                    }
-                    PieceType::Pawn(_, pawn_positionn, _) => {
-                        let pawn_index = pawn_positionn.to_index();
-                        if pawn_index == new_index + 7 || pawn_index == new_index + 9 {
-                            return true;
-                        }
-                    }
-                    _ => {
-                        if piece.can_move_to(position, board).is_ok() {
-                            return true;
-                        }
-                    }
-                }
+    other_pieces
+        .iter()
+        .filter(|piece| piece.position() != position)
+        .any(|piece| match piece {
+            PieceType::King(_, other_king_position, _) => {
+                other_king_position.distance(position) == 1
+            }
+            PieceType::Pawn(pawn_color, pawn_position, _) => {
+                let direction = match pawn_color {
+                    Color::White => 1,
+                    Color::Black => -1,
+                };
+                pawn_position.offset(1, direction) == Some(*position)
+                    || pawn_position.offset(-1, direction) == Some(*position)
             }
+            _ => matches!(
+                piece.can_move_to(*position, board),
+                Ok(()) | Err(ChessError::InvalidCapture)
+            ),
+        })
+}
 
-            false
-        }
+pub fn is_check(king: PieceType, board: &dyn BoardTrait) -> bool {
+    match king {
+        PieceType::King(color, position, _) => is_attacked(&position, &color, board),
         _ => false,
     }
 }
 
 pub fn can_king_move_safe_position(king: PieceType, board: &dyn BoardTrait) -> bool {
     match king {
-        PieceType::King(color, current_position) => {
-            let current_index = current_position.to_index();
-            //tempary board to check if king can move to safe position
-            let mut cloned = board.clone_as_a();
-            let tmp_board = cloned.as_mut();
-
-            tmp_board.square_mut(&current_position).piece = None;
-
-            for i in 7..10 {
-                if current_index < 63 {
-                    let square = tmp_board.square(&Position::from_index(current_index + i));
-                    if let Some(piece) = &square.piece {
-                        if piece.color() != color {
-                            let next_position = Position::new(square.x, square.y);
-                            tmp_board.square_mut(&next_position).piece = None;
-                            if !is_check(PieceType::King(color, next_position), tmp_board) {
-                                return false;
-                            }
-                        }
-                    }
-                }
-
-                if current_index > 0 {
-                    let square = tmp_board.square(&Position::from_index(current_index - i));
-                    if let Some(piece) = &square.piece {
-                        if piece.color() != color {
-                            let next_position = Position::new(square.x, square.y);
-                            tmp_board.square_mut(&next_position).piece = None;
-                            if !is_check(PieceType::King(color, next_position), tmp_board) {
-                                return false;
-                            }
-                        }
-                    }
-                }
-            }
-
-            if current_index < 63 {
-                let square = tmp_board.square(&Position::from_index(current_index + 1));
-                if let Some(piece) = &square.piece {
-                    if piece.color() != color {
-                        let next_position = Position::new(square.x, square.y);
-                        tmp_board.square_mut(&next_position).piece = None;
-                        if !is_check(PieceType::King(color, next_position), tmp_board) {
-                            return false;
-                        }
-                    }
-                }
-            }
-
-            if current_index > 0 {
-                let square = tmp_board.square(&Position::from_index(current_index - 1));
-                if let Some(piece) = &square.piece {
-                    if piece.color() != color {
-                        let next_position = Position::new(square.x, square.y);
-                        tmp_board.square_mut(&next_position).piece = None;
-                        if !is_check(PieceType::King(color, next_position), tmp_board) {
-                            return false;
-                        }
-                    }
-                }
-            }
-
-            true
+        PieceType::King(_, current_position, _) => {
+            !board.piece_legal_moves(&current_position).is_empty()
         }
         _ => false,
     }
@@ -190,19 +175,27 @@ pub fn can_king_move_safe_position(king: PieceType, board: &dyn BoardTrait) -> b
 pub fn possible_moves(
     current_position: &Position,
     color: &Color,
+    is_first_move: bool,
     board: &dyn BoardTrait,
 ) -> Vec<Position> {
     let mut positions = vec![];
-    let moves = [7, 8, 9, 1, -7, -8, -9, -1];
-    for m in moves.iter() {
-        if current_position.to_index() + *m < 0 || current_position.to_index() + *m >= 64 {
+    for (df, dr) in OFFSETS.iter() {
+        let Some(next_position) = current_position.offset(*df, *dr) else {
             continue;
-        }
-        let next_position = Position::from_index(current_position.to_index() + *m);
-        if can_move_to(current_position, color, next_position, board) == Ok(()) {
+        };
+        if can_move_to(current_position, color, is_first_move, next_position, board) == Ok(()) {
             positions.push(next_position);
         }
     }
+
+    if is_first_move {
+        for x in ['g', 'c'] {
+            let next_position = Position::new(x, current_position.y);
+            if can_move_to(current_position, color, is_first_move, next_position, board) == Ok(()) {
+                positions.push(next_position);
+            }
+        }
+    }
     positions
 }
 
@@ -214,7 +207,8 @@ mod test {
     }
 
     use crate::{
-        BoardTrait, Position, board,
+        BoardTrait, Position,
+        board::{self, BOARD_SQUARES},
         pieces::{ChessError, Color, Piece, PieceType, king::is_check},
     };
 
@@ -224,7 +218,7 @@ mod test {
     fn test_invalid_king_move() {
         init();
         let mut board = board::empty_board();
-        let mut king = PieceType::King(Color::White, Position::new('e', 4));
+        let mut king = PieceType::King(Color::White, Position::new('e', 4), false);
         board.square_mut(&Position::new('e', 4)).piece = Some(king);
 
         let result = king.move_to(Position::new('e', 6), &mut board);
@@ -260,11 +254,11 @@ mod test {
     fn test_king_valid_moves() {
         init();
         let mut board = board::empty_board();
-        let mut king = PieceType::King(Color::White, Position::new('e', 4));
+        let mut king = PieceType::King(Color::White, Position::new('e', 4), false);
         board.square_mut(&Position::new('e', 4)).piece = Some(king);
 
         let result = king.move_to(Position::new('e', 5), &mut board);
-        assert_eq!(result.is_ok(), true, "e4 King can move to e5");
+        assert!(result.is_ok(), "e4 King can move to e5");
 
         let _ = result.unwrap();
         let mut king = *board.get_piece(Position::new('e', 5)).unwrap();
@@ -274,39 +268,39 @@ mod test {
         let _ = result.unwrap();
         let mut king = *board.get_piece(Position::new('f', 6)).unwrap();
         let result = king.move_to(Position::new('g', 6), &mut board);
-        assert_eq!(result.is_ok(), true, "f6 King can move to g6");
+        assert!(result.is_ok(), "f6 King can move to g6");
 
         let _ = result.unwrap();
         let mut king = *board.get_piece(Position::new('g', 6)).unwrap();
         let result = king.move_to(Position::new('h', 5), &mut board);
-        assert_eq!(result.is_ok(), true, "g6 King can move to h5");
+        assert!(result.is_ok(), "g6 King can move to h5");
 
         let _ = result.unwrap();
         let mut king = *board.get_piece(Position::new('h', 5)).unwrap();
         let result = king.move_to(Position::new('h', 4), &mut board);
-        assert_eq!(result.is_ok(), true, "h5 King can move to h4");
+        assert!(result.is_ok(), "h5 King can move to h4");
 
         let _ = result.unwrap();
         let mut king = *board.get_piece(Position::new('h', 4)).unwrap();
         let result = king.move_to(Position::new('g', 4), &mut board);
-        assert_eq!(result.is_ok(), true, "h4 King can move to g4");
+        assert!(result.is_ok(), "h4 King can move to g4");
 
         let _ = result.unwrap();
         let mut king = *board.get_piece(Position::new('g', 4)).unwrap();
         let result = king.move_to(Position::new('f', 4), &mut board);
-        assert_eq!(result.is_ok(), true, "g4 King can move to f4");
+        assert!(result.is_ok(), "g4 King can move to f4");
 
         let _ = result.unwrap();
         let mut king = *board.get_piece(Position::new('f', 4)).unwrap();
         let result = king.move_to(Position::new('e', 3), &mut board);
-        assert_eq!(result.is_ok(), true, "f4 King can move to e3");
+        assert!(result.is_ok(), "f4 King can move to e3");
     }
 
     #[test]
     fn test_king_unsafe_move() {
         init();
         let mut board = board::empty_board();
-        let mut king = PieceType::King(Color::White, Position::new('e', 4));
+        let mut king = PieceType::King(Color::White, Position::new('e', 4), false);
         board.square_mut(&Position::new('e', 4)).piece = Some(king);
 
         let black_queen = PieceType::Queen(Color::Black, Position::new('f', 7));
@@ -328,7 +322,7 @@ mod test {
             "White King can't move to e5, unsafe by balck pawn"
         );
 
-        let black_king = PieceType::King(Color::Black, Position::new('d', 2));
+        let black_king = PieceType::King(Color::Black, Position::new('d', 2), false);
         board.square_mut(&Position::new('d', 2)).piece = Some(black_king);
         let result = king.move_to(Position::new('e', 5), &mut board);
         assert_eq!(
@@ -342,7 +336,7 @@ mod test {
     fn test_king_invalid_capture() {
         init();
         let mut board = board::new_board();
-        let mut king = PieceType::King(Color::White, Position::new('e', 1));
+        let mut king = PieceType::King(Color::White, Position::new('e', 1), true);
         board.square_mut(&Position::new('e', 1)).piece = Some(king);
 
         let result = king.move_to(Position::new('e', 2), &mut board);
@@ -357,26 +351,22 @@ mod test {
     fn test_valid_capture() {
         init();
         let mut board = board::empty_board();
-        let mut king = PieceType::King(Color::White, Position::new('e', 6));
+        let mut king = PieceType::King(Color::White, Position::new('e', 6), false);
         board.square_mut(&Position::new('e', 6)).piece = Some(king);
         board.square_mut(&Position::new('e', 1)).piece = None;
         let _black_pawn = PieceType::Pawn(Color::Black, Position::new('e', 7), false);
 
         let result = king.move_to(Position::new('d', 7), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "e6 White King can capture Black d7 Pawn"
-        );
+        assert!(result.is_ok(), "e6 White King can capture Black d7 Pawn");
         let _ = result.unwrap();
-        let piece = board.get_piece(Position::new('d', 7)).unwrap();
+        let _piece = board.get_piece(Position::new('d', 7)).unwrap();
     }
 
     #[test]
     fn test_king_check() {
         init();
         let mut board = board::empty_board();
-        let king = PieceType::King(Color::Black, Position::new('e', 8));
+        let king = PieceType::King(Color::Black, Position::new('e', 8), true);
         let white_queen = PieceType::Queen(Color::White, Position::new('f', 7));
         board.square_mut(&Position::new('e', 8)).piece = Some(king);
         board.square_mut(&Position::new('f', 7)).piece = Some(white_queen);
@@ -391,7 +381,7 @@ mod test {
     fn test_king_not_check() {
         init();
         let mut board = board::empty_board();
-        let king = PieceType::King(Color::White, Position::new('e', 6));
+        let king = PieceType::King(Color::White, Position::new('e', 6), false);
         let black_queen = PieceType::Queen(Color::Black, Position::new('f', 8));
         board.square_mut(&Position::new('e', 6)).piece = Some(king);
         board.square_mut(&Position::new('f', 8)).piece = Some(black_queen);
@@ -407,18 +397,219 @@ mod test {
         init();
         let mut board = board::empty_board();
         let position = Position::new('e', 4);
-        let king = PieceType::King(Color::White, position);
+        let king = PieceType::King(Color::White, position, false);
         board.square_mut(&position).piece = Some(king);
 
-        let moves = possible_moves(&position, &Color::White, &board);
+        let moves = possible_moves(&position, &Color::White, false, &board);
         assert_eq!(moves.len(), 8, "King can move to 8 positions");
     }
 
     #[test]
     fn king_test_0_possible_move() {
         init();
-        let mut board = board::new_board();
-        let moves = possible_moves(&Position::new('e', 1), &Color::White, &board);
+        let board = board::new_board();
+        let moves = possible_moves(&Position::new('e', 1), &Color::White, true, &board);
         assert_eq!(moves.len(), 0, "King can not move");
     }
+
+    fn castling_board(color: Color) -> impl BoardTrait {
+        let rank = match color {
+            Color::White => 1,
+            Color::Black => 8,
+        };
+        let mut board = board::empty_board();
+        for x in ['a', 'e', 'h'] {
+            let position = Position::new(x, rank);
+            let piece = match x {
+                'e' => PieceType::King(color, position, true),
+                _ => PieceType::Rook(color, position, true),
+            };
+            board.square_mut(&position).piece = Some(piece);
+        }
+        board
+    }
+
+    #[test]
+    fn test_white_king_side_castle() {
+        init();
+        let mut board = castling_board(Color::White);
+        let mut king = PieceType::King(Color::White, Position::new('e', 1), true);
+
+        let result = king.move_to(Position::new('g', 1), &mut board);
+        assert!(result.is_ok(), "e1 White King can castle to g1");
+        assert_eq!(
+            board.get_piece(Position::new('g', 1)),
+            Some(&PieceType::King(Color::White, Position::new('g', 1), false))
+        );
+        assert_eq!(
+            board.get_piece(Position::new('f', 1)),
+            Some(&PieceType::Rook(Color::White, Position::new('f', 1), false))
+        );
+        assert!(board.get_piece(Position::new('h', 1)).is_none());
+        assert!(board.get_piece(Position::new('e', 1)).is_none());
+    }
+
+    #[test]
+    fn test_black_queen_side_castle() {
+        init();
+        let mut board = castling_board(Color::Black);
+        let mut king = PieceType::King(Color::Black, Position::new('e', 8), true);
+
+        let result = king.move_to(Position::new('c', 8), &mut board);
+        assert!(result.is_ok(), "e8 Black King can castle to c8");
+        assert_eq!(
+            board.get_piece(Position::new('c', 8)),
+            Some(&PieceType::King(Color::Black, Position::new('c', 8), false))
+        );
+        assert_eq!(
+            board.get_piece(Position::new('d', 8)),
+            Some(&PieceType::Rook(Color::Black, Position::new('d', 8), false))
+        );
+        assert!(board.get_piece(Position::new('a', 8)).is_none());
+    }
+
+    #[test]
+    fn test_castle_blocked() {
+        init();
+        let mut board = board::new_board();
+        let mut king = PieceType::King(Color::White, Position::new('e', 1), true);
+
+        let result = king.move_to(Position::new('g', 1), &mut board);
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::BlockedMove,
+            "e1 White King can't castle through f1 Bishop and g1 Knight"
+        );
+
+        board.square_mut(&Position::new('b', 1)).piece = None;
+        board.square_mut(&Position::new('c', 1)).piece = None;
+        board.square_mut(&Position::new('d', 1)).piece = None;
+        board.square_mut(&Position::new('b', 1)).piece =
+            Some(PieceType::Knight(Color::White, Position::new('b', 1)));
+        let result = king.move_to(Position::new('c', 1), &mut board);
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::BlockedMove,
+            "e1 White King can't castle queen side with a b1 Knight"
+        );
+    }
+
+    #[test]
+    fn test_castle_out_of_through_and_into_check() {
+        init();
+        let mut king = PieceType::King(Color::White, Position::new('e', 1), true);
+        for x in ['e', 'f', 'g'] {
+            let mut board = castling_board(Color::White);
+            let position = Position::new(x, 8);
+            board.square_mut(&position).piece =
+                Some(PieceType::Rook(Color::Black, position, false));
+
+            let result = king.move_to(Position::new('g', 1), &mut board);
+            assert_eq!(
+                result.err().unwrap(),
+                ChessError::UnSafeKing,
+                "e1 White King can't castle while {}1 is attacked",
+                x
+            );
+        }
+
+        let mut board = castling_board(Color::White);
+        let position = Position::new('b', 8);
+        board.square_mut(&position).piece = Some(PieceType::Rook(Color::Black, position, false));
+        let result = king.move_to(Position::new('c', 1), &mut board);
+        assert!(
+            result.is_ok(),
+            "e1 White King can castle queen side while only b1 is attacked"
+        );
+    }
+
+    #[test]
+    fn test_castle_after_king_or_rook_moved() {
+        init();
+        let mut board = castling_board(Color::White);
+        let mut king = PieceType::King(Color::White, Position::new('e', 1), false);
+        board.square_mut(&Position::new('e', 1)).piece = Some(king);
+
+        let result = king.move_to(Position::new('g', 1), &mut board);
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::InvalidMove,
+            "e1 White King can't castle after it has moved"
+        );
+
+        let mut board = castling_board(Color::White);
+        let mut king = PieceType::King(Color::White, Position::new('e', 1), true);
+        let rook_position = Position::new('a', 1);
+        board.square_mut(&rook_position).piece =
+            Some(PieceType::Rook(Color::White, rook_position, false));
+
+        let result = king.move_to(Position::new('c', 1), &mut board);
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::InvalidMove,
+            "e1 White King can't castle with a rook that has moved"
+        );
+    }
+
+    #[test]
+    fn king_test_possible_castling_moves() {
+        init();
+        let board = castling_board(Color::White);
+        let moves = possible_moves(&Position::new('e', 1), &Color::White, true, &board);
+        assert!(moves.contains(&Position::new('g', 1)));
+        assert!(moves.contains(&Position::new('c', 1)));
+        assert_eq!(
+            moves.len(),
+            7,
+            "King can move to 5 squares and castle both sides"
+        );
+    }
+
+    #[test]
+    fn test_king_moves_from_edge_squares() {
+        init();
+        let board = board::empty_board();
+        for index in 0..BOARD_SQUARES {
+            let position = Position::from_index(index);
+            if position.file() % 7 != 0 && position.rank() % 7 != 0 {
+                continue;
+            }
+            let moves = possible_moves(&position, &Color::White, false, &board);
+            for to in moves.iter() {
+                assert_eq!(
+                    position.distance(to),
+                    1,
+                    "{} King should not wrap around the board to {}",
+                    position,
+                    to
+                );
+            }
+            let expected = if position.file() % 7 == 0 && position.rank() % 7 == 0 {
+                3
+            } else {
+                5
+            };
+            assert_eq!(
+                moves.len(),
+                expected,
+                "{} King should have {} moves",
+                position,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_king_does_not_wrap_from_a_file_to_h_file() {
+        init();
+        let mut board = board::empty_board();
+        let mut king = PieceType::King(Color::White, Position::new('a', 4), false);
+        board.square_mut(&Position::new('a', 4)).piece = Some(king);
+        let result = king.move_to(Position::new('h', 3), &mut board);
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::InvalidMove,
+            "a4 King should not be able to move to h3"
+        );
+    }
 }
@@ -1,9 +1,18 @@
-use std::ops::ControlFlow;
-
-use crate::{BoardTrait, Position, board::BOARD_SQUARES};
+use crate::{BoardTrait, Position};
 
 use super::{ChessError, Color, Piece, PieceType};
 
+pub const OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
 pub fn move_to(
     knight: &PieceType,
     position: Position,
@@ -11,17 +20,15 @@ pub fn move_to(
 ) -> Result<Option<PieceType>, ChessError> {
     match knight {
         PieceType::Knight(color, current_position) => {
-            can_move_to(&current_position, &color, position, board)?;
+            can_move_to(current_position, color, position, board)?;
 
             let captured_piece = board.square(&position).piece;
-            board.square_mut(&current_position).piece = None;
+            board.square_mut(current_position).piece = None;
             board.square_mut(&position).piece = Some(PieceType::Knight(*color, position));
 
             Ok(captured_piece)
         }
-        _ => {
-            return Err(ChessError::InvalidPiece);
-        }
+        _ => Err(ChessError::InvalidPiece),
     }
 }
 
@@ -31,23 +38,19 @@ pub fn can_move_to(
     position: Position,
     board: &dyn BoardTrait,
 ) -> Result<(), ChessError> {
-    let new_index = position.to_index();
-    if new_index < 0 || new_index >= BOARD_SQUARES {
-        return Err(ChessError::InvalidMove);
-    }
-    let old_index = current_position.to_index();
-
-    let jump = new_index - old_index;
-    let jump = jump.abs();
-    if jump != 6 && jump != 10 && jump != 15 && jump != 17 {
+    let jump = (
+        position.file() - current_position.file(),
+        position.rank() - current_position.rank(),
+    );
+    if !OFFSETS.contains(&jump) {
         return Err(ChessError::InvalidMove);
     }
 
     let square = board.square(&position);
-    if let Some(piece) = &square.piece {
-        if piece.color() == color {
-            return Err(ChessError::InvalidCapture);
-        }
+    if let Some(piece) = &square.piece
+        && piece.color() == color
+    {
+        return Err(ChessError::InvalidCapture);
     }
 
     Ok(())
@@ -58,39 +61,19 @@ pub fn possible_moves(
     color: &Color,
     board: &dyn BoardTrait,
 ) -> Vec<Position> {
-    let mut positions = vec![];
-    let moves = [6, 10, 15, 17, -6, -10, -15, -17];
-    for m in moves.iter() {
-        let next_position = Position::from_index(current_position.to_index() + *m);
-        if can_move_to(current_position, color, next_position, board) == Ok(()) {
-            positions.push(next_position);
-        }
-    }
-    positions
-}
-
-fn valide_move(
-    color: &Color,
-    board: &dyn BoardTrait,
-    next_position: &Position,
-    positions: &mut Vec<Position>,
-) -> ControlFlow<()> {
-    let square = &board.square(next_position);
-    if square.piece.is_some() {
-        if square.piece.as_ref().unwrap().color() != color {
-            positions.push(Position::new(square.x, square.y));
-        }
-        return ControlFlow::Break(());
-    }
-    positions.push(Position::new(square.x, square.y));
-    ControlFlow::Continue(())
+    OFFSETS
+        .iter()
+        .filter_map(|(df, dr)| current_position.offset(*df, *dr))
+        .filter(|next_position| can_move_to(current_position, color, *next_position, board).is_ok())
+        .collect()
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        BoardTrait, Position, board,
-        pieces::{ChessError, Color, Piece, PieceType},
+        BoardTrait, Position,
+        board::{self, BOARD_SQUARES},
+        pieces::{ChessError, Color, Piece, PieceType, knight::possible_moves},
     };
 
     fn init() {
@@ -125,77 +108,49 @@ mod test {
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 4));
         board.square_mut(&Position::new('d', 4)).piece = Some(knight);
         let result = knight.move_to(Position::new('e', 6), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "d4 Knight should be able to move to e6"
-        );
+        assert!(result.is_ok(), "d4 Knight should be able to move to e6");
 
         let _ = result.unwrap();
         board.square_mut(&Position::new('e', 6)).piece = None;
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 4));
         board.square_mut(&Position::new('d', 4)).piece = Some(knight);
         let result = knight.move_to(Position::new('f', 5), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "d4 Knight should be able to move to f5"
-        );
+        assert!(result.is_ok(), "d4 Knight should be able to move to f5");
 
         let _ = result.unwrap();
         board.square_mut(&Position::new('f', 5)).piece = None;
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 4));
         board.square_mut(&Position::new('d', 4)).piece = Some(knight);
         let result = knight.move_to(Position::new('f', 3), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "d4 Knight should be able to move to f3"
-        );
+        assert!(result.is_ok(), "d4 Knight should be able to move to f3");
 
         let _ = result.unwrap();
         board.square_mut(&Position::new('f', 3)).piece = None;
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 4));
         board.square_mut(&Position::new('d', 4)).piece = Some(knight);
         let result = knight.move_to(Position::new('e', 2), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "d4 Knight should be able to move to e2"
-        );
+        assert!(result.is_ok(), "d4 Knight should be able to move to e2");
 
         let _ = result.unwrap();
         board.square_mut(&Position::new('e', 2)).piece = None;
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 4));
         board.square_mut(&Position::new('d', 4)).piece = Some(knight);
         let result = knight.move_to(Position::new('c', 2), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "d4 Knight should be able to move to c2"
-        );
+        assert!(result.is_ok(), "d4 Knight should be able to move to c2");
 
         let _ = result.unwrap();
         board.square_mut(&Position::new('c', 2)).piece = None;
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 4));
         board.square_mut(&Position::new('d', 4)).piece = Some(knight);
         let result = knight.move_to(Position::new('b', 3), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "d4 Knight should be able to move to b3"
-        );
+        assert!(result.is_ok(), "d4 Knight should be able to move to b3");
 
         let _ = result.unwrap();
         board.square_mut(&Position::new('b', 3)).piece = None;
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 4));
         board.square_mut(&Position::new('d', 4)).piece = Some(knight);
         let result = knight.move_to(Position::new('b', 5), &mut board);
-        assert_eq!(
-            result.is_ok(),
-            true,
-            "d4 Knight should be able to move to b5"
-        );
+        assert!(result.is_ok(), "d4 Knight should be able to move to b5");
     }
 
     #[test]
@@ -222,10 +177,51 @@ mod test {
         let mut knight = PieceType::Knight(Color::White, Position::new('d', 5));
         board.square_mut(&Position::new('d', 5)).piece = Some(knight);
         let result = knight.move_to(Position::new('e', 7), &mut board);
-        assert_eq!(
+        assert!(
             result.is_ok(),
-            true,
             "d4 Knight should be able to capture black pawn at e7"
         );
     }
+
+    #[test]
+    fn test_knight_moves_from_edge_squares() {
+        init();
+        let board = board::empty_board();
+        for index in 0..BOARD_SQUARES {
+            let position = Position::from_index(index);
+            if position.file() % 7 != 0 && position.rank() % 7 != 0 {
+                continue;
+            }
+            let moves = possible_moves(&position, &Color::White, &board);
+            for to in moves.iter() {
+                let jump = (
+                    (to.file() - position.file()).abs(),
+                    (to.rank() - position.rank()).abs(),
+                );
+                assert!(
+                    jump == (1, 2) || jump == (2, 1),
+                    "{} Knight should not wrap around the board to {}",
+                    position,
+                    to
+                );
+            }
+            if position.file() % 7 == 0 && position.rank() % 7 == 0 {
+                assert_eq!(moves.len(), 2, "{} Knight should have 2 moves", position);
+            }
+        }
+    }
+
+    #[test]
+    fn test_knight_does_not_wrap_from_h1_to_a2() {
+        init();
+        let mut board = board::empty_board();
+        let mut knight = PieceType::Knight(Color::White, Position::new('h', 1));
+        board.square_mut(&Position::new('h', 1)).piece = Some(knight);
+        let result = knight.move_to(Position::new('a', 2), &mut board);
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::InvalidMove,
+            "h1 Knight should not be able to move to a2"
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use std::fmt::{Debug, Display};
 
-use crate::{Position, board::BoardTrait};
+use crate::{Move, Position, board::BoardTrait};
 
 use self::pawn::pawn_move_to;
 
@@ -11,7 +11,8 @@ pub mod pawn;
 pub mod queen;
 pub mod rook;
 
-pub type Value = u8;
+/// Material value in centipawns.
+pub type Value = i32;
 
 pub trait Piece: Debug + CloneAsPiece + 'static {
     fn move_to(
@@ -24,39 +25,57 @@ pub trait Piece: Debug + CloneAsPiece + 'static {
 
     fn color(&self) -> &Color;
 
-    fn possible_moves(&self, board: &dyn BoardTrait) -> Vec<Position>;
+    fn possible_moves(&self, board: &dyn BoardTrait) -> Vec<Move>;
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PieceType {
     Pawn(Color, Position, bool),
-    Rook(Color, Position),
+    Rook(Color, Position, bool),
     Bishop(Color, Position),
     Knight(Color, Position),
     Queen(Color, Position),
-    King(Color, Position),
+    King(Color, Position, bool),
 }
 
 impl PieceType {
+    /// Returns the material value in centipawns. The king can never be captured, so it is
+    /// worth nothing as material.
     pub fn value(&self) -> Value {
         match self {
-            PieceType::Pawn(_, _, _) => 1,
-            PieceType::Rook(_, _) => 5,
-            PieceType::Bishop(_, _) => 3,
-            PieceType::Knight(_, _) => 3,
-            PieceType::Queen(_, _) => 9,
-            PieceType::King(_, _) => u8::MAX,
+            PieceType::Pawn(_, _, _) => 100,
+            PieceType::Rook(_, _, _) => 500,
+            PieceType::Bishop(_, _) => 330,
+            PieceType::Knight(_, _) => 320,
+            PieceType::Queen(_, _) => 900,
+            PieceType::King(_, _, _) => 0,
+        }
+    }
+
+    /// Returns the piece letter, uppercase for white and lowercase for black.
+    pub fn symbol(&self) -> char {
+        let symbol = match self {
+            PieceType::Pawn(_, _, _) => 'p',
+            PieceType::Rook(_, _, _) => 'r',
+            PieceType::Bishop(_, _) => 'b',
+            PieceType::Knight(_, _) => 'n',
+            PieceType::Queen(_, _) => 'q',
+            PieceType::King(_, _, _) => 'k',
+        };
+        match self.color() {
+            Color::White => symbol.to_ascii_uppercase(),
+            Color::Black => symbol,
         }
     }
 
     pub fn position(&self) -> &Position {
         match self {
             PieceType::Pawn(_, position, _) => position,
-            PieceType::Rook(_, position) => position,
+            PieceType::Rook(_, position, _) => position,
             PieceType::Bishop(_, position) => position,
             PieceType::Knight(_, position) => position,
             PieceType::Queen(_, position) => position,
-            PieceType::King(_, position) => position,
+            PieceType::King(_, position, _) => position,
         }
     }
 }
@@ -68,12 +87,12 @@ impl Piece for PieceType {
         board: &mut dyn BoardTrait,
     ) -> Result<Option<PieceType>, ChessError> {
         match self {
-            PieceType::Pawn(_, _, _) => pawn_move_to(self, position, board),
-            PieceType::Rook(_, _) => rook::move_to(self, position, board),
+            PieceType::Pawn(_, _, _) => pawn_move_to(self, position, None, board),
+            PieceType::Rook(_, _, _) => rook::move_to(self, position, board),
             PieceType::Bishop(_, _) => bishop::move_to(self, position, board),
             PieceType::Knight(_, _) => knight::move_to(self, position, board),
             PieceType::Queen(_, _) => queen::move_to(self, position, board),
-            PieceType::King(_, _) => king::move_to(self, position, board),
+            PieceType::King(_, _, _) => king::move_to(self, position, board),
         }
     }
 
@@ -82,7 +101,7 @@ impl Piece for PieceType {
             PieceType::Pawn(color, current_position, is_first_move) => {
                 pawn::can_move_to(current_position, color, *is_first_move, position, board)
             }
-            PieceType::Rook(color, current_position) => {
+            PieceType::Rook(color, current_position, _) => {
                 rook::can_move_to(current_position, color, position, board)
             }
             PieceType::Bishop(color, current_position) => {
@@ -94,8 +113,8 @@ impl Piece for PieceType {
             PieceType::Queen(color, current_position) => {
                 queen::can_move_to(current_position, color, position, board)
             }
-            PieceType::King(color, current_position) => {
-                king::can_move_to(current_position, color, position, board)
+            PieceType::King(color, current_position, is_first_move) => {
+                king::can_move_to(current_position, color, *is_first_move, position, board)
             }
         }
     }
@@ -103,25 +122,32 @@ impl Piece for PieceType {
     fn color(&self) -> &Color {
         match self {
             PieceType::Pawn(color, _, _) => color,
-            PieceType::Rook(color, _) => color,
+            PieceType::Rook(color, _, _) => color,
             PieceType::Bishop(color, _) => color,
             PieceType::Knight(color, _) => color,
             PieceType::Queen(color, _) => color,
-            PieceType::King(color, _) => color,
+            PieceType::King(color, _, _) => color,
         }
     }
 
-    fn possible_moves(&self, board: &dyn BoardTrait) -> Vec<Position> {
-        match self {
+    fn possible_moves(&self, board: &dyn BoardTrait) -> Vec<Move> {
+        let positions = match self {
             PieceType::Pawn(color, position, is_first_move) => {
-                pawn::possible_moves(position, color, *is_first_move, board)
+                return pawn::possible_moves(position, color, *is_first_move, board);
             }
-            PieceType::Rook(color, position) => rook::possible_moves(position, color, board),
+            PieceType::Rook(color, position, _) => rook::possible_moves(position, color, board),
             PieceType::Bishop(color, position) => bishop::possible_moves(position, color, board),
             PieceType::Knight(color, position) => knight::possible_moves(position, color, board),
             PieceType::Queen(color, position) => queen::possible_moves(position, color, board),
-            PieceType::King(color, position) => king::possible_moves(position, color, board),
-        }
+            PieceType::King(color, position, is_first_move) => {
+                king::possible_moves(position, color, *is_first_move, board)
+            }
+        };
+
+        positions
+            .into_iter()
+            .map(|to| Move::new(*self.position(), to))
+            .collect()
     }
 }
 
@@ -129,11 +155,109 @@ impl Display for PieceType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             PieceType::Pawn(color, _, _) => write!(f, "{} Pawn", color),
-            PieceType::Rook(color, _) => write!(f, "{} Rook", color),
+            PieceType::Rook(color, _, _) => write!(f, "{} Rook", color),
             PieceType::Bishop(color, _) => write!(f, "{} Bishop", color),
             PieceType::Knight(color, _) => write!(f, "{} Knight", color),
             PieceType::Queen(color, _) => write!(f, "{} Queen", color),
-            PieceType::King(color, _) => write!(f, "{} King", color),
+            PieceType::King(color, _, _) => write!(f, "{} King", color),
+        }
+    }
+}
+
+/// Checks that `position` can be reached from `current_position` by sliding along one of
+/// `directions` without jumping over another piece.
+pub fn sliding_move(
+    board: &dyn BoardTrait,
+    current_position: &Position,
+    position: &Position,
+    directions: &[(i8, i8)],
+) -> Result<(), ChessError> {
+    let df = position.file() - current_position.file();
+    let dr = position.rank() - current_position.rank();
+    let direction = (df.signum(), dr.signum());
+    if direction == (0, 0)
+        || (df != 0 && dr != 0 && df.abs() != dr.abs())
+        || !directions.contains(&direction)
+    {
+        return Err(ChessError::InvalidMove);
+    }
+
+    for next_position in current_position.ray(direction.0, direction.1) {
+        if next_position == *position {
+            break;
+        }
+        if board.square(&next_position).piece.is_some() {
+            return Err(ChessError::BlockedMove);
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects every square reachable along `directions`, stopping at the first piece and
+/// including it when it can be captured.
+pub fn sliding_moves(
+    current_position: &Position,
+    color: &Color,
+    directions: &[(i8, i8)],
+    board: &dyn BoardTrait,
+) -> Vec<Position> {
+    let mut positions = vec![];
+    for (df, dr) in directions {
+        for next_position in current_position.ray(*df, *dr) {
+            if let Some(piece) = &board.square(&next_position).piece {
+                if piece.color() != color {
+                    positions.push(next_position);
+                }
+                break;
+            }
+            positions.push(next_position);
+        }
+    }
+    positions
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Promotion {
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+}
+
+impl Promotion {
+    pub const ALL: [Promotion; 4] = [
+        Promotion::Queen,
+        Promotion::Rook,
+        Promotion::Bishop,
+        Promotion::Knight,
+    ];
+
+    pub fn piece(&self, color: Color, position: Position) -> PieceType {
+        match self {
+            Promotion::Queen => PieceType::Queen(color, position),
+            Promotion::Rook => PieceType::Rook(color, position, false),
+            Promotion::Bishop => PieceType::Bishop(color, position),
+            Promotion::Knight => PieceType::Knight(color, position),
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Promotion> {
+        match c.to_ascii_lowercase() {
+            'q' => Some(Promotion::Queen),
+            'r' => Some(Promotion::Rook),
+            'b' => Some(Promotion::Bishop),
+            'n' => Some(Promotion::Knight),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(&self) -> char {
+        match self {
+            Promotion::Queen => 'q',
+            Promotion::Rook => 'r',
+            Promotion::Bishop => 'b',
+            Promotion::Knight => 'n',
         }
     }
 }
@@ -154,6 +278,15 @@ pub enum Color {
     White,
 }
 
+impl Color {
+    pub fn opponent(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -173,17 +306,11 @@ pub enum ChessError {
     NoPiece,
 }
 
-impl<'a> PartialEq<Color> for &'a Color {
+impl PartialEq<Color> for &Color {
     fn eq(&self, other: &Color) -> bool {
         match self {
-            Color::Black => match other {
-                Color::Black => true,
-                _ => false,
-            },
-            Color::White => match other {
-                Color::White => true,
-                _ => false,
-            },
+            Color::Black => matches!(other, Color::Black),
+            Color::White => matches!(other, Color::White),
         }
     }
 }
@@ -1,34 +1,52 @@
 use std::borrow::BorrowMut;
 
 use crate::{
-    Position,
+    Move, Position,
     board::BoardTrait,
     pieces::{ChessError, Piece},
 };
 
-use super::{Color, PieceType};
+use super::{Color, PieceType, Promotion};
 
 pub fn pawn_move_to(
     pawn: &PieceType,
     position: Position,
+    promotion: Option<Promotion>,
     board: &mut dyn BoardTrait,
 ) -> Result<Option<PieceType>, ChessError> {
     match pawn {
         PieceType::Pawn(color, current_position, is_first_move) => {
-            can_move_to(&current_position, &color, *is_first_move, position, board)?;
+            can_move_to(current_position, color, *is_first_move, position, board)?;
 
-            let captured_piece = board.square_mut(&position).piece;
-            board.square_mut(&current_position).piece = None;
-            board.borrow_mut().square_mut(&position).piece =
-                Some(PieceType::Pawn(*color, position, false));
-
-            //TODO: Pawn promotion
+            let mut captured_piece = board.square_mut(&position).piece;
+            if is_en_passant(current_position, color, &position, board) {
+                let passed_position = Position::new(position.x, current_position.y);
+                captured_piece = board.square_mut(&passed_position).piece.take();
+            }
 
-            return Ok(captured_piece);
-        }
-        _ => {
-            return Err(ChessError::InvalidPiece);
+            let piece = if position.y == last_rank(color) {
+                promotion
+                    .unwrap_or(Promotion::Queen)
+                    .piece(*color, position)
+            } else {
+                PieceType::Pawn(*color, position, false)
+            };
+            board.square_mut(current_position).piece = None;
+            board.borrow_mut().square_mut(&position).piece = Some(piece);
+
+            let en_passant = if (position.y - current_position.y).abs() == 2 {
+                Some(Position::new(
+                    position.x,
+                    (position.y + current_position.y) / 2,
+                ))
+            } else {
+                None
+            };
+            board.set_en_passant(en_passant);
+
+            Ok(captured_piece)
         }
+        _ => Err(ChessError::InvalidPiece),
     }
 }
 
@@ -39,48 +57,75 @@ pub fn can_move_to(
     position: Position,
     board: &dyn BoardTrait,
 ) -> Result<(), ChessError> {
-    let new_index = position.to_index();
-    let old_index = current_position.to_index();
-    let jump = new_index - old_index;
+    let direction = forward(color);
+    let df = position.file() - current_position.file();
+    let dr = position.rank() - current_position.rank();
 
-    match color {
-        Color::Black => {
-            if jump > 0 {
+    let square = board.square(&position);
+    match (df.abs(), dr * direction) {
+        (0, 1) => {
+            if square.piece.is_some() {
                 return Err(ChessError::InvalidMove);
             }
         }
-        Color::White => {
-            if jump < 0 {
+        (0, 2) => {
+            let passed_position = Position::new(current_position.x, current_position.y + direction);
+            if !is_first_move
+                || square.piece.is_some()
+                || board.square(&passed_position).piece.is_some()
+            {
                 return Err(ChessError::InvalidMove);
             }
         }
-    }
-    let jump = jump.abs();
-    if jump != 8 && jump != 16 && jump != 7 && jump != 9 {
-        return Err(ChessError::InvalidMove);
+        (1, 1) => {
+            if square.piece.is_none() && !is_en_passant(current_position, color, &position, board) {
+                return Err(ChessError::InvalidMove);
+            }
+        }
+        _ => return Err(ChessError::InvalidMove),
     }
 
-    if !is_first_move && jump == 16 {
+    let other_piece = &square.piece;
+    if let Some(other_piece) = other_piece
+        && other_piece.color() == color
+    {
         return Err(ChessError::InvalidMove);
     }
 
-    let square = board.square(&position);
-    if (jump == 8 || jump == 16) && square.piece.is_some() {
-        return Err(ChessError::InvalidMove);
-    }
+    Ok(())
+}
 
-    if (jump == 7 || jump == 9) && square.piece.is_none() {
-        return Err(ChessError::InvalidMove);
+/// Returns true if a `color` pawn moving from `current_position` to `position` captures en passant.
+pub fn is_en_passant(
+    current_position: &Position,
+    color: &Color,
+    position: &Position,
+    board: &dyn BoardTrait,
+) -> bool {
+    if board.en_passant() != Some(*position) || current_position.x == position.x {
+        return false;
     }
 
-    let other_piece = &square.piece;
-    if let Some(other_piece) = other_piece {
-        if other_piece.color() == color {
-            return Err(ChessError::InvalidMove);
-        }
+    let passed_position = Position::new(position.x, current_position.y);
+    matches!(
+        board.square(&passed_position).piece,
+        Some(PieceType::Pawn(passed_color, _, _)) if passed_color != *color
+    )
+}
+
+/// Returns the rank direction a `color` pawn moves in.
+pub fn forward(color: &Color) -> i8 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
     }
+}
 
-    Ok(())
+pub fn last_rank(color: &Color) -> i8 {
+    match color {
+        Color::White => 8,
+        Color::Black => 1,
+    }
 }
 
 pub fn possible_moves(
@@ -88,30 +133,45 @@ pub fn possible_moves(
     color: &Color,
     is_first_move: bool,
     board: &dyn BoardTrait,
-) -> Vec<Position> {
+) -> Vec<Move> {
     let mut positions = vec![];
-    let moves = match (color, is_first_move) {
-        (Color::Black, true) => [-8, -16, -7, -9].to_vec(),
-        (Color::Black, false) => [-8, -7, -9].to_vec(),
-        (Color::White, true) => [8, 16, 7, 9].to_vec(),
-        (Color::White, false) => [8, 7, 9].to_vec(),
-    };
+    let direction = forward(color);
+    let moves = [
+        (0, direction),
+        (0, 2 * direction),
+        (1, direction),
+        (-1, direction),
+    ];
+
+    for (df, dr) in moves.iter() {
+        let Some(next_position) = current_position.offset(*df, *dr) else {
+            continue;
+        };
+        if can_move_to(current_position, color, is_first_move, next_position, board) != Ok(()) {
+            continue;
+        }
 
-    for m in moves.iter() {
-        let next_position = Position::from_index(current_position.to_index() + *m);
-        if can_move_to(current_position, color, is_first_move, next_position, board) == Ok(()) {
-            positions.push(next_position);
+        if next_position.y == last_rank(color) {
+            for promotion in Promotion::ALL {
+                positions.push(Move::with_promotion(
+                    *current_position,
+                    next_position,
+                    promotion,
+                ));
+            }
+        } else {
+            positions.push(Move::new(*current_position, next_position));
         }
     }
 
-    return positions;
+    positions
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        BoardTrait, Position, board,
-        pieces::{ChessError, Color, Piece, PieceType, pawn::possible_moves},
+        BoardTrait, Move, Position, board,
+        pieces::{ChessError, Color, Piece, PieceType, Promotion, pawn::possible_moves},
     };
 
     fn init() {
@@ -399,4 +459,224 @@ mod test {
         let possible_moves = possible_moves(&Position::new('d', 7), &Color::Black, false, &board);
         assert_eq!(possible_moves.len(), 1);
     }
+
+    #[test]
+    fn test_double_move_sets_en_passant() {
+        init();
+        let mut board = board::new_board();
+        let _ = board
+            .move_piece(Position::new('e', 2), Position::new('e', 4))
+            .unwrap();
+        assert_eq!(board.en_passant(), Some(Position::new('e', 3)));
+
+        let _ = board
+            .move_piece(Position::new('d', 7), Position::new('d', 6))
+            .unwrap();
+        assert_eq!(board.en_passant(), None);
+
+        let _ = board
+            .move_piece(Position::new('d', 6), Position::new('d', 5))
+            .unwrap();
+        let _ = board
+            .move_piece(Position::new('g', 1), Position::new('f', 3))
+            .unwrap();
+        assert_eq!(
+            board.en_passant(),
+            None,
+            "Non pawn moves clear the en passant square"
+        );
+    }
+
+    #[test]
+    fn test_white_en_passant_capture() {
+        init();
+        let mut board = board::new_board();
+        let white_pawn = PieceType::Pawn(Color::White, Position::new('e', 5), false);
+        board.square_mut(&Position::new('e', 2)).piece = None;
+        board.square_mut(&Position::new('e', 5)).piece = Some(white_pawn);
+
+        let _ = board
+            .move_piece(Position::new('d', 7), Position::new('d', 5))
+            .unwrap();
+        let moves = possible_moves(&Position::new('e', 5), &Color::White, false, &board);
+        assert!(moves.contains(&Move::new(Position::new('e', 5), Position::new('d', 6))));
+
+        let captured = board.move_piece(Position::new('e', 5), Position::new('d', 6));
+        assert_eq!(
+            captured,
+            Ok(Some(PieceType::Pawn(
+                Color::Black,
+                Position::new('d', 5),
+                false
+            ))),
+            "White pawn should capture d5 black pawn en passant"
+        );
+        assert!(board.get_piece(Position::new('d', 5)).is_none());
+        assert_eq!(
+            board.get_piece(Position::new('d', 6)).unwrap().color(),
+            Color::White
+        );
+    }
+
+    #[test]
+    fn test_black_en_passant_capture() {
+        init();
+        let mut board = board::new_board();
+        let mut black_pawn = PieceType::Pawn(Color::Black, Position::new('b', 4), false);
+        board.square_mut(&Position::new('b', 7)).piece = None;
+        board.square_mut(&Position::new('b', 4)).piece = Some(black_pawn);
+
+        let _ = board
+            .move_piece(Position::new('c', 2), Position::new('c', 4))
+            .unwrap();
+        let captured = black_pawn.move_to(Position::new('c', 3), &mut board);
+        assert_eq!(
+            captured,
+            Ok(Some(PieceType::Pawn(
+                Color::White,
+                Position::new('c', 4),
+                false
+            )))
+        );
+        assert!(board.get_piece(Position::new('c', 4)).is_none());
+    }
+
+    #[test]
+    fn test_en_passant_expires() {
+        init();
+        let mut board = board::new_board();
+        let white_pawn = PieceType::Pawn(Color::White, Position::new('e', 5), false);
+        board.square_mut(&Position::new('e', 2)).piece = None;
+        board.square_mut(&Position::new('e', 5)).piece = Some(white_pawn);
+
+        let _ = board
+            .move_piece(Position::new('d', 7), Position::new('d', 5))
+            .unwrap();
+        let _ = board
+            .move_piece(Position::new('g', 1), Position::new('f', 3))
+            .unwrap();
+        let _ = board
+            .move_piece(Position::new('g', 8), Position::new('f', 6))
+            .unwrap();
+
+        let result = board.move_piece(Position::new('e', 5), Position::new('d', 6));
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::InvalidMove,
+            "En passant is only possible right after the double move"
+        );
+    }
+
+    #[test]
+    fn test_white_pawn_promotes_to_queen_by_default() {
+        init();
+        let mut board = board::empty_board();
+        let mut pawn = PieceType::Pawn(Color::White, Position::new('e', 7), false);
+        board.square_mut(&Position::new('e', 7)).piece = Some(pawn);
+
+        let result = pawn.move_to(Position::new('e', 8), &mut board);
+        assert!(result.is_ok());
+        assert_eq!(
+            board.get_piece(Position::new('e', 8)),
+            Some(&PieceType::Queen(Color::White, Position::new('e', 8)))
+        );
+    }
+
+    #[test]
+    fn test_black_pawn_under_promotion_with_capture() {
+        init();
+        let mut board = board::new_board();
+        let black_pawn = PieceType::Pawn(Color::Black, Position::new('b', 2), false);
+        board.square_mut(&Position::new('b', 2)).piece = Some(black_pawn);
+
+        let m = Move::with_promotion(
+            Position::new('b', 2),
+            Position::new('a', 1),
+            Promotion::Knight,
+        );
+        let captured = board.make_move(&m);
+        assert_eq!(
+            captured,
+            Ok(Some(PieceType::Rook(
+                Color::White,
+                Position::new('a', 1),
+                true
+            )))
+        );
+        assert_eq!(
+            board.get_piece(Position::new('a', 1)),
+            Some(&PieceType::Knight(Color::Black, Position::new('a', 1)))
+        );
+    }
+
+    #[test]
+    fn test_possible_promotion_moves() {
+        init();
+        let mut board = board::empty_board();
+        let position = Position::new('g', 7);
+        board.square_mut(&position).piece = Some(PieceType::Pawn(Color::White, position, false));
+        board.square_mut(&Position::new('h', 8)).piece =
+            Some(PieceType::Rook(Color::Black, Position::new('h', 8), true));
+
+        let moves = possible_moves(&position, &Color::White, false, &board);
+        assert_eq!(
+            moves.len(),
+            8,
+            "Two target squares with four promotions each"
+        );
+        for promotion in Promotion::ALL {
+            assert!(moves.contains(&Move::with_promotion(
+                position,
+                Position::new('g', 8),
+                promotion
+            )));
+            assert!(moves.contains(&Move::with_promotion(
+                position,
+                Position::new('h', 8),
+                promotion
+            )));
+        }
+    }
+
+    #[test]
+    fn test_pawn_does_not_capture_across_board_edge() {
+        init();
+        let mut board = board::empty_board();
+        let position = Position::new('a', 4);
+        board.square_mut(&position).piece = Some(PieceType::Pawn(Color::White, position, false));
+        board.square_mut(&Position::new('h', 4)).piece =
+            Some(PieceType::Pawn(Color::Black, Position::new('h', 4), false));
+
+        let moves = possible_moves(&position, &Color::White, false, &board);
+        assert_eq!(
+            moves,
+            vec![Move::new(position, Position::new('a', 5))],
+            "a4 Pawn should not capture on the h-file"
+        );
+
+        let position = Position::new('h', 5);
+        board.square_mut(&position).piece = Some(PieceType::Pawn(Color::Black, position, false));
+        board.square_mut(&Position::new('a', 5)).piece =
+            Some(PieceType::Pawn(Color::White, Position::new('a', 5), false));
+        let moves = possible_moves(&position, &Color::Black, false, &board);
+        assert!(
+            !moves.iter().any(|m| m.to.x == 'a'),
+            "h5 Pawn should not capture on the a-file"
+        );
+    }
+
+    #[test]
+    fn test_pawn_double_move_blocked_by_piece_in_between() {
+        init();
+        let mut board = board::new_board();
+        board.square_mut(&Position::new('e', 3)).piece =
+            Some(PieceType::Knight(Color::Black, Position::new('e', 3)));
+
+        let result = board.move_piece(Position::new('e', 2), Position::new('e', 4));
+        assert_eq!(
+            result.err().unwrap(),
+            ChessError::InvalidMove,
+            "Pawn should not jump over a piece on its double move"
+        );
+    }
 }
@@ -1,9 +1,17 @@
-use crate::{
-    BoardTrait, Position,
-    pieces::{bishop::bishop_move, rook::rook_move},
-};
+use crate::{BoardTrait, Position};
 
-use super::{ChessError, Color, Piece, PieceType, bishop, rook};
+use super::{ChessError, Color, Piece, PieceType, sliding_move, sliding_moves};
+
+pub const DIRECTIONS: [(i8, i8); 8] = [
+    (1, 1),
+    (1, -1),
+    (-1, -1),
+    (-1, 1),
+    (0, 1),
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+];
 
 pub fn move_to(
     queen: &PieceType,
@@ -12,17 +20,15 @@ pub fn move_to(
 ) -> Result<Option<PieceType>, ChessError> {
     match queen {
         PieceType::Queen(color, current_position) => {
-            can_move_to(&current_position, &color, position, board)?;
+            can_move_to(current_position, color, position, board)?;
 
             let captured_piece = board.square_mut(&position).piece;
-            board.square_mut(&current_position).piece = None;
+            board.square_mut(current_position).piece = None;
             board.square_mut(&position).piece = Some(PieceType::Queen(*color, position));
 
             Ok(captured_piece)
         }
-        _ => {
-            return Err(ChessError::InvalidPiece);
-        }
+        _ => Err(ChessError::InvalidPiece),
     }
 }
 
@@ -32,29 +38,13 @@ pub fn can_move_to(
     position: Position,
     board: &dyn BoardTrait,
 ) -> Result<(), ChessError> {
-    let new_index = position.to_index();
-    let old_index = current_position.to_index();
-
-    let jump = new_index - old_index;
-    if jump % 7 != 0 && jump % 9 != 0 && jump % 8 != 0 && jump / 8 != 0 {
-        return Err(ChessError::InvalidMove);
-    }
-
-    if jump.abs() < 7 && position.y != current_position.y {
-        return Err(ChessError::InvalidMove);
-    }
-
-    if jump % 8 == 0 || (jump / 8 == 0 && position.y == current_position.y) {
-        rook_move(board, old_index, new_index, jump)?;
-    } else if jump % 7 == 0 || jump % 9 == 0 {
-        bishop_move(board, old_index, new_index, jump)?;
-    }
+    sliding_move(board, current_position, &position, &DIRECTIONS)?;
 
     let square = &board.square(&position);
-    if let Some(piece) = &square.piece {
-        if piece.color() == color {
-            return Err(ChessError::InvalidCapture);
-        }
+    if let Some(piece) = &square.piece
+        && piece.color() == color
+    {
+        return Err(ChessError::InvalidCapture);
     }
 
     Ok(())
@@ -65,16 +55,14 @@ pub fn possible_moves(
     color: &Color,
     board: &dyn BoardTrait,
 ) -> Vec<Position> {
-    let mut bishop_positions = bishop::possible_moves(current_position, color, board);
-    let rook_positions = rook::possible_moves(current_position, color, board);
-    bishop_positions.extend(rook_positions);
-    bishop_positions
+    sliding_moves(current_position, color, &DIRECTIONS, board)
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        BoardTrait, Position, board,
+        BoardTrait, Position,
+        board::{self, BOARD_SQUARES},
         pieces::{
             ChessError, Color, Piece, PieceType,
             queen::{can_move_to, possible_moves},
@@ -338,6 +326,32 @@ mod test {
 
         let board = board::new_board();
         let positions = possible_moves(&Position::new('d', 4), &Color::White, &board);
-        assert_eq!(positions.len(), 16);
+        assert_eq!(positions.len(), 19);
+    }
+
+    #[test]
+    fn test_queen_moves_from_edge_squares() {
+        init();
+        let board = board::empty_board();
+        for index in 0..BOARD_SQUARES {
+            let position = Position::from_index(index);
+            if position.file() % 7 != 0 && position.rank() % 7 != 0 {
+                continue;
+            }
+            let moves = possible_moves(&position, &Color::White, &board);
+            for to in moves.iter() {
+                let df = (to.file() - position.file()).abs();
+                let dr = (to.rank() - position.rank()).abs();
+                assert!(
+                    df == 0 || dr == 0 || df == dr,
+                    "{} Queen should not wrap around the board to {}",
+                    position,
+                    to
+                );
+            }
+            if position.file() % 7 == 0 && position.rank() % 7 == 0 {
+                assert_eq!(moves.len(), 21, "{} Queen should have 21 moves", position);
+            }
+        }
     }
 }
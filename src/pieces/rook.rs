@@ -1,8 +1,10 @@
-use std::{borrow::BorrowMut, ops::ControlFlow};
+use std::borrow::BorrowMut;
 
-use crate::{BoardTrait, Position, board::BOARD_SQUARES};
+use crate::{BoardTrait, Position};
 
-use super::{ChessError, Color, Piece, PieceType};
+use super::{ChessError, Color, Piece, PieceType, sliding_move, sliding_moves};
+
+pub const DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
 
 pub fn move_to(
     rook: &PieceType,
@@ -10,19 +12,17 @@ pub fn move_to(
     board: &mut dyn BoardTrait,
 ) -> Result<Option<PieceType>, ChessError> {
     match rook {
-        PieceType::Rook(color, current_position) => {
-            can_move_to(&current_position, &color, position, board)?;
+        PieceType::Rook(color, current_position, _) => {
+            can_move_to(current_position, color, position, board)?;
 
             let captured_piece = board.square_mut(&position).piece;
-            board.square_mut(&current_position).piece = None;
+            board.square_mut(current_position).piece = None;
             board.borrow_mut().square_mut(&position).piece =
-                Some(PieceType::Rook(*color, position));
+                Some(PieceType::Rook(*color, position, false));
 
             Ok(captured_piece)
         }
-        _ => {
-            return Err(ChessError::InvalidPiece);
-        }
+        _ => Err(ChessError::InvalidPiece),
     }
 }
 
@@ -32,146 +32,32 @@ pub fn can_move_to(
     position: Position,
     board: &dyn BoardTrait,
 ) -> Result<(), ChessError> {
-    let new_index = position.to_index();
-    let old_index = current_position.to_index();
-
-    let jump = new_index - old_index;
-
-    if jump.abs() < 8 && position.y != current_position.y {
-        return Err(ChessError::InvalidMove);
-    }
-
-    if jump % 8 != 0 && jump / 8 != 0 {
-        return Err(ChessError::InvalidMove);
-    }
-
-    rook_move(board, old_index, new_index, jump)?;
+    sliding_move(board, current_position, &position, &DIRECTIONS)?;
 
     let square = &board.square(&position);
-    if square.piece.is_some() {
-        if square.piece.as_ref().unwrap().color() == color {
-            return Err(ChessError::InvalidCapture);
-        }
+    if let Some(piece) = &square.piece
+        && piece.color() == color
+    {
+        return Err(ChessError::InvalidCapture);
     }
 
     Ok(())
 }
 
-pub fn rook_move(
-    board: &dyn BoardTrait,
-    old_index: i32,
-    new_index: i32,
-    jump: i32,
-) -> Result<(), ChessError> {
-    if jump % 8 == 0 {
-        let mut index = old_index;
-        if new_index > old_index {
-            index += 8;
-            while index != new_index {
-                let square = &board.square(&Position::from_index(index));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index += 8;
-            }
-        } else {
-            index -= 8;
-            while index != new_index {
-                let square = &board.square(&Position::from_index(index));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index -= 8;
-            }
-        }
-    } else {
-        let mut index = old_index;
-        if new_index > old_index {
-            index += 1;
-            while index != new_index {
-                let square = &board.square(&Position::from_index(index));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index += 1;
-            }
-        } else {
-            index -= 1;
-            while index != new_index {
-                let square = &board.square(&Position::from_index(index));
-                if square.piece.is_some() {
-                    return Err(ChessError::BlockedMove);
-                }
-                index -= 1;
-            }
-        }
-    }
-    Ok(())
-}
-
 pub fn possible_moves(
     current_position: &Position,
     color: &Color,
     board: &dyn BoardTrait,
 ) -> Vec<Position> {
-    let current_index = current_position.to_index();
-    let mut next_inndex = current_index + 8;
-    let mut positions = vec![];
-    while next_inndex < BOARD_SQUARES {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex += 8;
-    }
-
-    let mut next_inndex = current_index + 1;
-    while next_inndex % 8 != 0 {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex += 1;
-    }
-
-    let mut next_inndex = current_index - 8;
-    while next_inndex >= 0 {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex -= 8;
-    }
-
-    let mut next_inndex = current_index - 1;
-    while next_inndex % 8 != 7 {
-        if let ControlFlow::Break(_) = valide_move(color, board, next_inndex, &mut positions) {
-            break;
-        }
-        next_inndex -= 1;
-    }
-    positions
-}
-
-fn valide_move(
-    color: &Color,
-    board: &dyn BoardTrait,
-    next_inndex: i32,
-    positions: &mut Vec<Position>,
-) -> ControlFlow<()> {
-    let square = board.square(&Position::from_index(next_inndex));
-    if square.piece.is_some() {
-        if square.piece.as_ref().unwrap().color() != color {
-            positions.push(Position::new(square.x, square.y));
-        }
-        return ControlFlow::Break(());
-    }
-    positions.push(Position::new(square.x, square.y));
-    ControlFlow::Continue(())
+    sliding_moves(current_position, color, &DIRECTIONS, board)
 }
 
 #[cfg(test)]
 mod test {
 
     use crate::{
-        BoardTrait, Position, board,
+        BoardTrait, Position,
+        board::{self, BOARD_SQUARES},
         pieces::{ChessError, Color, Piece, PieceType, rook::possible_moves},
     };
 
@@ -183,7 +69,7 @@ mod test {
     fn test_white_rook_invalid_move() {
         init();
         let mut board = board::empty_board();
-        let mut rook = PieceType::Rook(Color::White, Position::new('d', 4));
+        let mut rook = PieceType::Rook(Color::White, Position::new('d', 4), false);
         board.square_mut(&Position::new('d', 4)).piece = Some(rook);
 
         let new_board = rook.move_to(Position::new('e', 5), &mut board);
@@ -219,7 +105,7 @@ mod test {
     fn test_black_rook_invalid_initial_move() {
         init();
         let mut board = board::new_board();
-        let mut left_rook = PieceType::Rook(Color::Black, Position::new('a', 8));
+        let mut left_rook = PieceType::Rook(Color::Black, Position::new('a', 8), true);
         board.square_mut(&Position::new('a', 8)).piece = Some(left_rook);
 
         let new_board = left_rook.move_to(Position::new('a', 7), &mut board);
@@ -236,7 +122,7 @@ mod test {
             "Black left Rook can't move to b7"
         );
 
-        let mut right_rook = PieceType::Rook(Color::Black, Position::new('h', 8));
+        let mut right_rook = PieceType::Rook(Color::Black, Position::new('h', 8), true);
         board.square_mut(&Position::new('h', 8)).piece = Some(right_rook);
 
         let new_board = right_rook.move_to(Position::new('h', 7), &mut board);
@@ -271,7 +157,7 @@ mod test {
             index += 1;
             board.square_mut(&Position::from_index(index)).piece = None;
         }
-        let mut left_rook = PieceType::Rook(Color::White, Position::new('a', 1));
+        let mut left_rook = PieceType::Rook(Color::White, Position::new('a', 1), true);
         board.square_mut(&Position::new('a', 1)).piece = Some(left_rook);
 
         let new_board = left_rook.move_to(Position::new('a', 8), &mut board);
@@ -299,7 +185,7 @@ mod test {
             index += 1;
             board.square_mut(&Position::from_index(index)).piece = None;
         }
-        let mut left_rook = PieceType::Rook(Color::White, Position::new('a', 1));
+        let mut left_rook = PieceType::Rook(Color::White, Position::new('a', 1), true);
         board.square_mut(&Position::new('a', 1)).piece = Some(left_rook);
 
         let new_piece = left_rook.move_to(Position::new('a', 7), &mut board);
@@ -312,7 +198,7 @@ mod test {
         let left_rook = board.get_piece(Position::new('a', 7)).unwrap();
         assert_eq!(left_rook.color(), Color::White, "White left rook is in a7");
 
-        let mut left_rook = PieceType::Rook(Color::White, Position::new('a', 7));
+        let mut left_rook = PieceType::Rook(Color::White, Position::new('a', 7), false);
         board.square_mut(&Position::new('a', 7)).piece = Some(left_rook);
         let new_piece = left_rook.move_to(Position::new('b', 7), &mut board);
 
@@ -338,4 +224,26 @@ mod test {
             "White Rook should have 14 possible moves"
         );
     }
+
+    #[test]
+    fn test_rook_moves_from_edge_squares() {
+        init();
+        let board = board::empty_board();
+        for index in 0..BOARD_SQUARES {
+            let position = Position::from_index(index);
+            if position.file() % 7 != 0 && position.rank() % 7 != 0 {
+                continue;
+            }
+            let moves = possible_moves(&position, &Color::White, &board);
+            for to in moves.iter() {
+                assert!(
+                    to.file() == position.file() || to.rank() == position.rank(),
+                    "{} Rook should not wrap around the board to {}",
+                    position,
+                    to
+                );
+            }
+            assert_eq!(moves.len(), 14, "{} Rook should have 14 moves", position);
+        }
+    }
 }
@@ -0,0 +1,336 @@
+use std::fmt::Display;
+
+use crate::{
+    Move, Position,
+    board::{BoardTrait, GameStatus},
+    pieces::{Color, Piece, PieceType, Promotion, king},
+};
+
+/// Writes `m` in Standard Algebraic Notation for the position on `board` before the move.
+pub fn to_san(board: &dyn BoardTrait, m: &Move) -> String {
+    let Some(piece) = board.get_piece(m.from).copied() else {
+        return m.to_string();
+    };
+
+    let mut san = String::new();
+    match piece {
+        PieceType::King(_, _, _) if king::is_castling(&m.from, &m.to) && m.from.x == 'e' => {
+            san.push_str(if m.to.x == 'g' { "O-O" } else { "O-O-O" });
+        }
+        PieceType::Pawn(_, _, _) => {
+            if m.from.x != m.to.x {
+                san.push(m.from.x);
+                san.push('x');
+            }
+            san.push_str(&m.to.to_string());
+            if let Some(promotion) = m.promotion {
+                san.push('=');
+                san.push(promotion.to_char().to_ascii_uppercase());
+            }
+        }
+        _ => {
+            san.push(piece.symbol().to_ascii_uppercase());
+            san.push_str(&disambiguation(board, &piece, m));
+            if board.get_piece(m.to).is_some() {
+                san.push('x');
+            }
+            san.push_str(&m.to.to_string());
+        }
+    }
+
+    let mut next_board = board.clone_as_a();
+    if next_board.make_move(m).is_ok() {
+        match next_board.status(&piece.color().opponent()) {
+            GameStatus::Checkmate => san.push('#'),
+            GameStatus::Check => san.push('+'),
+            GameStatus::Ongoing | GameStatus::Stalemate => {}
+        }
+    }
+
+    san
+}
+
+/// Returns the file, rank or square needed to tell `m` apart from the same kind of piece
+/// reaching the same square.
+fn disambiguation(board: &dyn BoardTrait, piece: &PieceType, m: &Move) -> String {
+    let others: Vec<Position> = board
+        .legal_moves(piece.color())
+        .into_iter()
+        .filter(|other| other.to == m.to && other.from != m.from)
+        .filter(|other| {
+            board
+                .get_piece(other.from)
+                .is_some_and(|other_piece| other_piece.symbol() == piece.symbol())
+        })
+        .map(|other| other.from)
+        .collect();
+
+    if others.is_empty() {
+        String::new()
+    } else if others.iter().all(|other| other.x != m.from.x) {
+        m.from.x.to_string()
+    } else if others.iter().all(|other| other.y != m.from.y) {
+        m.from.y.to_string()
+    } else {
+        m.from.to_string()
+    }
+}
+
+/// Resolves a SAN move such as `Nf3`, `exd5`, `O-O` or `e8=Q+` against the legal moves of
+/// `color` on `board`.
+pub fn parse_san(board: &dyn BoardTrait, color: &Color, san: &str) -> Result<Move, SanError> {
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    if text.is_empty() {
+        return Err(SanError::Empty);
+    }
+
+    let legal_moves = board.legal_moves(color);
+    let rank = match color {
+        Color::White => 1,
+        Color::Black => 8,
+    };
+    let castling = match text {
+        "O-O" | "0-0" => Some(Position::new('g', rank)),
+        "O-O-O" | "0-0-0" => Some(Position::new('c', rank)),
+        _ => None,
+    };
+    if let Some(to) = castling {
+        let from = Position::new('e', rank);
+        return legal_moves
+            .into_iter()
+            .find(|m| {
+                m.from == from
+                    && m.to == to
+                    && matches!(board.get_piece(from), Some(PieceType::King(_, _, _)))
+            })
+            .ok_or_else(|| SanError::IllegalMove(san.to_string()));
+    }
+
+    let mut chars: Vec<char> = text.chars().collect();
+    let symbol = match chars.first() {
+        Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => {
+            let symbol = c.to_ascii_lowercase();
+            chars.remove(0);
+            symbol
+        }
+        _ => 'p',
+    };
+
+    let mut promotion = None;
+    if symbol == 'p'
+        && let Some(last) = chars.last()
+        && let Some(piece) = Promotion::from_char(*last).filter(|_| last.is_ascii_uppercase())
+    {
+        promotion = Some(piece);
+        chars.pop();
+        if chars.last() == Some(&'=') {
+            chars.pop();
+        }
+    }
+
+    if chars.len() < 2 {
+        return Err(SanError::InvalidSyntax(san.to_string()));
+    }
+    let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let to: Position = to
+        .parse()
+        .map_err(|_| SanError::InvalidSyntax(san.to_string()))?;
+
+    let is_capture = chars.last() == Some(&'x');
+    if is_capture {
+        chars.pop();
+    }
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars {
+        match c {
+            'a'..='h' if from_file.is_none() => from_file = Some(c),
+            '1'..='8' if from_rank.is_none() => from_rank = c.to_digit(10).map(|y| y as i8),
+            _ => return Err(SanError::InvalidSyntax(san.to_string())),
+        }
+    }
+    if symbol == 'p' && from_file.is_none() {
+        from_file = Some(to.x);
+    }
+
+    let candidates: Vec<Move> = legal_moves
+        .into_iter()
+        .filter(|m| {
+            m.to == to
+                && m.promotion == promotion
+                && from_file.is_none_or(|x| m.from.x == x)
+                && from_rank.is_none_or(|y| m.from.y == y)
+                && board
+                    .get_piece(m.from)
+                    .is_some_and(|piece| piece.symbol().to_ascii_lowercase() == symbol)
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [m] => Ok(*m),
+        [] => Err(SanError::IllegalMove(san.to_string())),
+        _ => Err(SanError::AmbiguousMove(san.to_string())),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SanError {
+    Empty,
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanError::Empty => write!(f, "no move given"),
+            SanError::InvalidSyntax(san) => write!(f, "'{}' is not a move in SAN", san),
+            SanError::IllegalMove(san) => write!(f, "'{}' is not a legal move", san),
+            SanError::AmbiguousMove(san) => {
+                write!(f, "'{}' matches more than one legal move", san)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{board, fen::Fen};
+
+    fn san(fen: &str, from: &str, to: &str) -> String {
+        let fen: Fen = fen.parse().unwrap();
+        let m = Move::new(from.parse().unwrap(), to.parse().unwrap());
+        to_san(fen.board.as_ref(), &m)
+    }
+
+    #[test]
+    fn test_pawn_and_piece_moves() {
+        let board = board::new_board();
+        let m = Move::new(Position::new('e', 2), Position::new('e', 4));
+        assert_eq!(to_san(&board, &m), "e4");
+        let m = Move::new(Position::new('g', 1), Position::new('f', 3));
+        assert_eq!(to_san(&board, &m), "Nf3");
+    }
+
+    #[test]
+    fn test_captures() {
+        let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+        assert_eq!(san(fen, "e4", "d5"), "exd5");
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 3";
+        assert_eq!(san(fen, "e5", "d6"), "exd6");
+        let fen = "4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1";
+        assert_eq!(san(fen, "d1", "d5"), "Qxd5");
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        let fen = "4k3/8/8/8/8/8/8/R4RK1 w - - 0 1";
+        assert_eq!(san(fen, "a1", "d1"), "Rad1");
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san(fen, "a1", "a3"), "R1a3");
+        let fen = "4k3/8/8/8/8/2N1N3/8/2N1K3 w - - 0 1";
+        assert_eq!(san(fen, "c3", "d5"), "Ncd5");
+        assert_eq!(san(fen, "e3", "d1"), "Ned1");
+        assert_eq!(san(fen, "e3", "g2"), "Ng2");
+        let fen = "8/7k/8/8/Q2Q4/8/8/Q6K w - - 0 1";
+        assert_eq!(san(fen, "a4", "d1"), "Qa4d1");
+    }
+
+    #[test]
+    fn test_castling_promotion_check_and_mate() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(fen, "e1", "g1"), "O-O");
+        assert_eq!(san(fen, "e1", "c1"), "O-O-O");
+
+        let fen: Fen = "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        let m = Move::with_promotion(
+            Position::new('a', 7),
+            Position::new('b', 8),
+            Promotion::Queen,
+        );
+        assert_eq!(to_san(fen.board.as_ref(), &m), "axb8=Q+");
+
+        let fen = "rnbqkbnr/ppppp2p/5p2/6p1/4P3/8/PPPP1PPP/RNBQKBNR w KQkq g6 0 3";
+        assert_eq!(san(fen, "d1", "h5"), "Qh5#");
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let fen: Fen = "r3k2r/1P6/8/3p4/4P3/2N3N1/8/R3K2R w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let board = fen.board.as_ref();
+        let parse = |san: &str| parse_san(board, &Color::White, san);
+
+        assert_eq!(
+            parse("exd5"),
+            Ok(Move::new(Position::new('e', 4), Position::new('d', 5)))
+        );
+        assert_eq!(
+            parse("e5"),
+            Ok(Move::new(Position::new('e', 4), Position::new('e', 5)))
+        );
+        assert_eq!(
+            parse("Ne2"),
+            Err(SanError::AmbiguousMove("Ne2".to_string()))
+        );
+        assert_eq!(
+            parse("Nge2"),
+            Ok(Move::new(Position::new('g', 3), Position::new('e', 2)))
+        );
+        assert_eq!(
+            parse("Nxd5"),
+            Ok(Move::new(Position::new('c', 3), Position::new('d', 5)))
+        );
+        assert_eq!(
+            parse("Ngxd5"),
+            Err(SanError::IllegalMove("Ngxd5".to_string()))
+        );
+        assert_eq!(
+            parse("O-O+"),
+            Ok(Move::new(Position::new('e', 1), Position::new('g', 1)))
+        );
+        assert_eq!(
+            parse("0-0-0"),
+            Ok(Move::new(Position::new('e', 1), Position::new('c', 1)))
+        );
+        assert_eq!(
+            parse("bxa8=N"),
+            Ok(Move::with_promotion(
+                Position::new('b', 7),
+                Position::new('a', 8),
+                Promotion::Knight
+            ))
+        );
+        assert_eq!(
+            parse("b8Q"),
+            Ok(Move::with_promotion(
+                Position::new('b', 7),
+                Position::new('b', 8),
+                Promotion::Queen
+            ))
+        );
+        assert_eq!(parse("b8"), Err(SanError::IllegalMove("b8".to_string())));
+        assert_eq!(
+            parse("Zz9"),
+            Err(SanError::InvalidSyntax("Zz9".to_string()))
+        );
+        assert_eq!(parse(""), Err(SanError::Empty));
+    }
+
+    #[test]
+    fn test_san_round_trip_on_all_legal_moves() {
+        let fen: Fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let board = fen.board.as_ref();
+        for m in board.legal_moves(&Color::White) {
+            let san = to_san(board, &m);
+            assert_eq!(parse_san(board, &Color::White, &san), Ok(m), "{}", san);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::mem::size_of;
+
+use crate::{Move, ai::Score};
+
+/// Hash size used until the user asks for another one.
+pub const DEFAULT_HASH_MB: usize = 16;
+const BYTES_PER_MB: usize = 1024 * 1024;
+
+/// How a stored score relates to the real score of the position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The search failed high, the real score is at least this.
+    Lower,
+    /// The search failed low, the real score is at most this.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u8,
+    pub bound: Bound,
+    /// Score of the position, with mate scores counted from the position rather than the root.
+    pub score: Score,
+    pub best_move: Option<Move>,
+}
+
+/// A fixed-size hash table of search results indexed by Zobrist key.
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    /// Creates a table using at most `megabytes` of memory, rounded down to a power of two
+    /// number of entries.
+    pub fn new(megabytes: usize) -> Self {
+        let entries = (megabytes * BYTES_PER_MB / size_of::<Option<Entry>>()).max(1);
+        let entries = 1 << entries.ilog2();
+        TranspositionTable {
+            entries: vec![None; entries],
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&Entry> {
+        self.entries[self.index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    /// Stores `entry`, keeping a deeper result of the same position.
+    pub fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+        if let Some(old) = slot
+            && old.key == entry.key
+            && old.depth > entry.depth
+        {
+            return;
+        }
+        *slot = Some(entry);
+    }
+
+    /// Returns how full the table is in permille, sampled from its first thousand slots.
+    pub fn hashfull(&self) -> u16 {
+        let sample = self.entries.len().min(1000);
+        let used = self.entries[..sample]
+            .iter()
+            .filter(|entry| entry.is_some())
+            .count();
+        (used * 1000 / sample) as u16
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Position;
+
+    fn entry(key: u64, depth: u8, score: Score) -> Entry {
+        Entry {
+            key,
+            depth,
+            bound: Bound::Exact,
+            score,
+            best_move: Some(Move::new(Position::new('e', 2), Position::new('e', 4))),
+        }
+    }
+
+    #[test]
+    fn test_size_in_megabytes() {
+        let table = TranspositionTable::new(1);
+        assert!(table.capacity().is_power_of_two());
+        assert!(table.capacity() * size_of::<Option<Entry>>() <= BYTES_PER_MB);
+        assert!(table.capacity() * 2 * size_of::<Option<Entry>>() > BYTES_PER_MB);
+        assert_eq!(TranspositionTable::new(0).capacity(), 1);
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(table.probe(42), None);
+
+        table.store(entry(42, 3, 15));
+        assert_eq!(table.probe(42), Some(&entry(42, 3, 15)));
+        let other = 42 + table.capacity() as u64;
+        assert_eq!(table.probe(other), None, "Same slot, different position");
+
+        table.store(entry(42, 2, 30));
+        assert_eq!(table.probe(42).unwrap().score, 15, "Deeper result is kept");
+        table.store(entry(other, 1, 30));
+        assert_eq!(
+            table.probe(other).unwrap().score,
+            30,
+            "Other positions replace"
+        );
+        assert_eq!(table.probe(42), None);
+
+        table.clear();
+        assert_eq!(table.probe(other), None);
+        assert_eq!(table.hashfull(), 0);
+    }
+}
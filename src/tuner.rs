@@ -0,0 +1,303 @@
+use std::{fmt::Display, thread};
+
+use crate::{
+    ai::{Engine, Score},
+    board::BoardTrait,
+    eval::Weights,
+    fen::{Fen, FenError},
+    pieces::Color,
+};
+
+/// Fewest positions worth a thread of their own when computing the error.
+const MIN_CHUNK: usize = 1024;
+/// Largest scaling constant `find_k` tries.
+const MAX_K: f64 = 10.0;
+
+/// A quiet position labelled with the result of the game it was played in.
+#[derive(Debug)]
+pub struct Sample {
+    pub board: Box<dyn BoardTrait>,
+    /// 1 for a white win, 0.5 for a draw and 0 for a black win.
+    pub result: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TunerError {
+    InvalidFen(usize, FenError),
+    MissingResult(usize),
+}
+
+impl Display for TunerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TunerError::InvalidFen(line, err) => write!(f, "line {}: {}", line, err),
+            TunerError::MissingResult(line) => write!(f, "line {}: no game result", line),
+        }
+    }
+}
+
+impl std::error::Error for TunerError {}
+
+/// Reads a game result from White's point of view, written as `1-0`, `0-1` or `1/2-1/2`, or as
+/// `1.0`, `0.5` or `0.0`. Quotes, brackets and semicolons around it are ignored.
+fn parse_result(token: &str) -> Option<f64> {
+    match token.trim_matches(|c| matches!(c, '"' | '[' | ']' | ';')) {
+        "1-0" | "1.0" => Some(1.0),
+        "1/2-1/2" | "0.5" => Some(0.5),
+        "0-1" | "0.0" => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Parses a labelled position: a FEN record, or the four fields of an EPD record, followed by
+/// the game result, as in `<fen> [0.5]` or `<epd> c9 "1-0";`.
+pub fn parse_line(line: &str) -> Result<(Fen, f64), TunerError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let counters = fields.len() >= 6 && fields[4..6].iter().all(|f| f.parse::<u32>().is_ok());
+    let (fen, rest) = fields.split_at(if counters { 6 } else { 4.min(fields.len()) });
+    let fen = fen
+        .join(" ")
+        .parse()
+        .map_err(|err| TunerError::InvalidFen(0, err))?;
+    let result = rest
+        .iter()
+        .find_map(|token| parse_result(token))
+        .ok_or(TunerError::MissingResult(0))?;
+    Ok((fen, result))
+}
+
+/// Loads labelled positions, one per line, and resolves their captures with a quiescence
+/// search under `weights`, so the evaluation is only tuned on quiet positions. Empty lines are
+/// skipped.
+pub fn load(text: &str, weights: &Weights) -> Result<Vec<Sample>, TunerError> {
+    let mut engine = Engine::new(1);
+    let mut samples = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (fen, result) = parse_line(line).map_err(|err| match err {
+            TunerError::InvalidFen(_, err) => TunerError::InvalidFen(index + 1, err),
+            TunerError::MissingResult(_) => TunerError::MissingResult(index + 1),
+        })?;
+
+        let (_, captures) = engine.quiescence(fen.board.as_ref(), fen.turn, weights);
+        let mut board = fen.board;
+        for m in captures.iter() {
+            if board.make_move(m).is_err() {
+                break;
+            }
+        }
+        samples.push(Sample { board, result });
+    }
+    Ok(samples)
+}
+
+/// Maps a score from White's point of view to an expected result, scaled by `k`.
+pub fn sigmoid(score: Score, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+/// Returns the mean squared difference between the results of `samples` and the results
+/// expected from their evaluation with `weights`.
+pub fn error(samples: &mut [Sample], weights: &Weights, k: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk = samples.len().div_ceil(threads).max(MIN_CHUNK);
+    let count = samples.len() as f64;
+    let total: f64 = thread::scope(|scope| {
+        let workers: Vec<_> = samples
+            .chunks_mut(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|sample| {
+                            let score = weights.evaluate(sample.board.as_ref(), Color::White);
+                            (sample.result - sigmoid(score, k)).powi(2)
+                        })
+                        .sum::<f64>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .sum()
+    });
+    total / count
+}
+
+/// Finds the scaling constant of `sigmoid` that fits `weights` to `samples` best. It is kept
+/// while tuning, so the weights stay in centipawns.
+pub fn find_k(samples: &mut [Sample], weights: &Weights) -> f64 {
+    let mut k = 1.0;
+    let mut best = error(samples, weights, k);
+    let mut step = 0.5;
+    while step > 0.001 {
+        let mut improved = false;
+        for candidate in [k - step, k + step] {
+            if candidate <= 0.0 || candidate > MAX_K {
+                continue;
+            }
+            let candidate_error = error(samples, weights, candidate);
+            if candidate_error < best {
+                best = candidate_error;
+                k = candidate;
+                improved = true;
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+    k
+}
+
+/// Tunes `weights` to `samples` by local search: every weight is moved up or down by one
+/// while that lowers the error. Stops after `passes` passes over all weights or once a pass
+/// finds no improvement, calling `on_pass` with the error after each pass.
+pub fn tune(
+    samples: &mut [Sample],
+    mut weights: Weights,
+    k: f64,
+    passes: usize,
+    mut on_pass: impl FnMut(usize, f64, &Weights),
+) -> Weights {
+    let mut params = weights.params();
+    let mut best = error(samples, &weights, k);
+    for pass in 1..=passes {
+        let mut improved = false;
+        for index in 0..params.len() {
+            for delta in [1, -1] {
+                params[index] += delta;
+                weights.set_params(&params);
+                let candidate = error(samples, &weights, k);
+                if candidate < best {
+                    best = candidate;
+                    improved = true;
+                    break;
+                }
+                params[index] -= delta;
+            }
+        }
+        weights.set_params(&params);
+        on_pass(pass, best, &weights);
+        if !improved {
+            break;
+        }
+    }
+    weights
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let (fen, result) =
+            parse_line("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 [0.5]")
+                .unwrap();
+        assert_eq!(fen.turn, Color::Black);
+        assert_eq!(result, 0.5);
+
+        let (_, result) = parse_line("4k3/8/8/8/8/8/8/4KQ2 w - - c9 \"1-0\";").unwrap();
+        assert_eq!(result, 1.0);
+        let (_, result) = parse_line("4k3/8/8/8/8/8/8/4KQ2 w - - 0 1 0-1").unwrap();
+        assert_eq!(result, 0.0);
+
+        assert_eq!(
+            parse_line("4k3/8/8/8/8/8/8/4KQ2 w - - 0 1").unwrap_err(),
+            TunerError::MissingResult(0)
+        );
+        assert!(matches!(
+            load(
+                "\n4k3/8/8/8/8/8/8/4KQ2 w - - 1-0\n8/8 w - - 1-0",
+                &Weights::default()
+            ),
+            Err(TunerError::InvalidFen(3, _))
+        ));
+    }
+
+    #[test]
+    fn test_load_resolves_captures() {
+        let samples = load(
+            "4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1 [1.0]",
+            &Weights::default(),
+        )
+        .unwrap();
+        assert_eq!(samples.len(), 1);
+        assert!(samples[0].board.get_piece("d5".parse().unwrap()).is_some());
+        assert_eq!(samples[0].board.get_all_black_pieces().len(), 1);
+        assert_eq!(samples[0].result, 1.0);
+    }
+
+    #[test]
+    fn test_load_resolves_captures_under_the_weights() {
+        let text = "4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1 [1.0]";
+        let samples = load(text, &Weights::default()).unwrap();
+        assert!(
+            samples[0].board.get_piece("d1".parse().unwrap()).is_some(),
+            "The queen is worth more than the defended pawn"
+        );
+
+        let mut weights = Weights::default();
+        weights.material_mg[4] = 0;
+        weights.material_eg[4] = 0;
+        let samples = load(text, &weights).unwrap();
+        assert!(
+            samples[0].board.get_piece("d1".parse().unwrap()).is_none(),
+            "A worthless queen takes the pawn"
+        );
+    }
+
+    #[test]
+    fn test_sigmoid() {
+        assert_eq!(sigmoid(0, 1.0), 0.5);
+        assert!((sigmoid(400, 1.0) - 10.0 / 11.0).abs() < 1e-9);
+        assert!((sigmoid(-150, 1.3) + sigmoid(150, 1.3) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_find_k() {
+        let mut samples = load(
+            "4k3/8/8/8/8/8/8/3QK3 w - - 0 1 [1.0]\n\
+             4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 [0.5]",
+            &Weights::default(),
+        )
+        .unwrap();
+        let weights = Weights::default();
+        let k = find_k(&mut samples, &weights);
+        assert!(k > 0.0 && k < MAX_K);
+        let best = error(&mut samples, &weights, k);
+        assert!(error(&mut samples, &weights, k * 0.8) > best);
+        assert!(error(&mut samples, &weights, k * 1.2) > best);
+    }
+
+    #[test]
+    fn test_tuning_lowers_the_error() {
+        let mut samples = load(
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 [1.0]\n\
+             4k3/4p3/8/8/8/8/8/4K3 w - - 0 1 [0.0]\n\
+             4k3/8/8/8/8/8/8/4KN2 w - - 0 1 [0.5]",
+            &Weights::default(),
+        )
+        .unwrap();
+        let weights = Weights::default();
+        let before = error(&mut samples, &weights, 1.0);
+        let mut errors = Vec::new();
+        let tuned = tune(&mut samples, weights.clone(), 1.0, 1, |_, error, _| {
+            errors.push(error)
+        });
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0] < before);
+        assert_eq!(error(&mut samples, &tuned, 1.0), errors[0]);
+        assert!(
+            tuned.material_eg[1] < weights.material_eg[1],
+            "A lone knight can not win"
+        );
+    }
+}
@@ -0,0 +1,561 @@
+use std::{
+    io::{BufRead, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    Game,
+    ai::{Engine, SearchInfo, SearchLimits, SearchOptions},
+    book::{Book, DEFAULT_BOOK_DEPTH, MAX_BOOK_DEPTH, Selection},
+    eval,
+    pieces::Color,
+    tt::DEFAULT_HASH_MB,
+};
+
+/// Time kept back from every move so the engine does not lose on time to communication lag.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+/// Number of moves the remaining time is split over when the GUI does not send `movestogo`.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+const MAX_HASH_MB: usize = 4096;
+
+/// Parameters of a `go` command.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GoParams {
+    pub depth: Option<u8>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    pub fn parse(args: &[&str]) -> Self {
+        let mut params = GoParams::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut number = || args.next().and_then(|value| value.parse::<u64>().ok());
+            match *arg {
+                "depth" => params.depth = number().map(|depth| depth.min(u8::MAX as u64) as u8),
+                "movetime" => params.movetime = number().map(Duration::from_millis),
+                "wtime" => params.wtime = number().map(Duration::from_millis),
+                "btime" => params.btime = number().map(Duration::from_millis),
+                "winc" => params.winc = number().map(Duration::from_millis),
+                "binc" => params.binc = number().map(Duration::from_millis),
+                "movestogo" => params.movestogo = number().map(|moves| moves as u32),
+                "infinite" => params.infinite = true,
+                _ => {}
+            }
+        }
+        params
+    }
+
+    /// Turns the clock of `color` into search limits.
+    pub fn limits(&self, color: Color) -> SearchLimits {
+        if self.infinite {
+            return SearchLimits::default();
+        }
+
+        let (time, increment) = match color {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        };
+        let time = match (self.movetime, time) {
+            (Some(movetime), _) => Some(movetime.saturating_sub(MOVE_OVERHEAD)),
+            (None, Some(time)) => {
+                let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+                let budget = time / moves_to_go + increment.unwrap_or_default() / 2;
+                Some(budget.min(time.saturating_sub(MOVE_OVERHEAD)))
+            }
+            (None, None) => None,
+        };
+
+        SearchLimits {
+            depth: self.depth,
+            time,
+        }
+    }
+}
+
+/// A Universal Chess Interface session that writes its replies to `output`.
+pub struct Uci<W: Write + Send + 'static> {
+    game: Game,
+    engine: Arc<Mutex<Engine>>,
+    output: Arc<Mutex<W>>,
+    stop: Arc<AtomicBool>,
+    /// Dropped on `stop` to wake an infinite search that is waiting to report its move.
+    stopped: Option<Sender<()>>,
+    search: Option<JoinHandle<()>>,
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    pub fn new(output: W) -> Self {
+        Uci {
+            game: Game::new(),
+            engine: Arc::new(Mutex::new(Engine::new(DEFAULT_HASH_MB))),
+            output: Arc::new(Mutex::new(output)),
+            stop: Arc::new(AtomicBool::new(false)),
+            stopped: None,
+            search: None,
+        }
+    }
+
+    /// Handles one command line, returning false once the GUI has sent `quit`.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, args)) = args.split_first() else {
+            return true;
+        };
+
+        match *command {
+            "uci" => {
+                send(
+                    &self.output,
+                    &format!("id name rchess {}", env!("CARGO_PKG_VERSION")),
+                );
+                send(&self.output, "id author keaz");
+                send(
+                    &self.output,
+                    &format!(
+                        "option name Hash type spin default {} min 1 max {}",
+                        DEFAULT_HASH_MB, MAX_HASH_MB
+                    ),
+                );
+                send(
+                    &self.output,
+                    "option name BookFile type string default <empty>",
+                );
+                send(
+                    &self.output,
+                    &format!(
+                        "option name BookDepth type spin default {} min 0 max {}",
+                        DEFAULT_BOOK_DEPTH, MAX_BOOK_DEPTH
+                    ),
+                );
+                send(
+                    &self.output,
+                    &format!(
+                        "option name BookSelection type combo default {} var {}",
+                        Selection::default(),
+                        Selection::NAMES.join(" var ")
+                    ),
+                );
+                // The defaults, read without the engine lock a running search holds.
+                let options = SearchOptions::default();
+                for name in SearchOptions::NAMES {
+                    let enabled = options.get(name).unwrap_or_default();
+                    send(
+                        &self.output,
+                        &format!("option name {} type check default {}", name, enabled),
+                    );
+                }
+                send(&self.output, "uciok");
+            }
+            "isready" => send(&self.output, "readyok"),
+            "setoption" => {
+                self.stop_search();
+                if let Err(err) = self.set_option(args) {
+                    send(&self.output, &format!("info string {}", err));
+                }
+            }
+            "ucinewgame" => {
+                self.stop_search();
+                self.game = Game::new();
+                self.engine.lock().unwrap().clear();
+            }
+            "position" => {
+                self.stop_search();
+                if let Err(err) = self.position(args) {
+                    send(&self.output, &format!("info string {}", err));
+                }
+            }
+            "go" => {
+                self.stop_search();
+                self.go(GoParams::parse(args));
+            }
+            "stop" => self.stop_search(),
+            "eval" => {
+                let trace = eval::trace(self.game.board.as_ref());
+                for line in trace.to_string().lines() {
+                    send(&self.output, line);
+                }
+            }
+            "quit" => {
+                self.stop_search();
+                return false;
+            }
+            _ => send(
+                &self.output,
+                &format!("info string unknown command '{}'", command),
+            ),
+        }
+
+        true
+    }
+
+    fn position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_at = args.iter().position(|arg| *arg == "moves");
+        let (setup, moves) = match moves_at {
+            Some(index) => (&args[..index], &args[index + 1..]),
+            None => (args, &[][..]),
+        };
+
+        let mut game = match setup.split_first() {
+            Some((&"startpos", _)) => Game::new(),
+            Some((&"fen", fen)) => Game::from_fen(&fen.join(" ")).map_err(|err| err.to_string())?,
+            _ => return Err("expected 'position startpos' or 'position fen'".to_string()),
+        };
+        for m in moves {
+            let parsed = game.parse_move(m).map_err(|err| err.to_string())?;
+            game.make_move(&parsed)
+                .map_err(|_| format!("illegal move {}", m))?;
+        }
+
+        self.game = game;
+        Ok(())
+    }
+
+    /// Handles `setoption name <id> [value <x>]`.
+    fn set_option(&mut self, args: &[&str]) -> Result<(), String> {
+        let value_at = args.iter().position(|arg| *arg == "value");
+        let (name, value) = match value_at {
+            Some(index) => (&args[..index], args[index + 1..].join(" ")),
+            None => (args, String::new()),
+        };
+        let name = match name.split_first() {
+            Some((&"name", name)) => name.join(" "),
+            _ => return Err("expected 'setoption name <id> [value <x>]'".to_string()),
+        };
+
+        let mut engine = self.engine.lock().unwrap();
+        if name.eq_ignore_ascii_case("hash") {
+            let megabytes = value
+                .parse::<usize>()
+                .ok()
+                .filter(|megabytes| (1..=MAX_HASH_MB).contains(megabytes))
+                .ok_or_else(|| format!("invalid Hash value '{}'", value))?;
+            engine.set_hash_size(megabytes);
+            return Ok(());
+        }
+        if name.eq_ignore_ascii_case("bookfile") {
+            let book = match value.as_str() {
+                "" | "<empty>" => None,
+                path => Some(Book::open(path).map_err(|err| format!("invalid BookFile: {}", err))?),
+            };
+            engine.set_book(book);
+            return Ok(());
+        }
+        if name.eq_ignore_ascii_case("bookdepth") {
+            engine.book_options.depth = value
+                .parse::<usize>()
+                .ok()
+                .filter(|depth| *depth <= MAX_BOOK_DEPTH)
+                .ok_or_else(|| format!("invalid BookDepth value '{}'", value))?;
+            return Ok(());
+        }
+        if name.eq_ignore_ascii_case("bookselection") {
+            engine.book_options.selection = value.parse().map_err(|err| format!("{}", err))?;
+            return Ok(());
+        }
+        if engine.options.get(&name).is_none() {
+            return Err(format!("unknown option '{}'", name));
+        }
+        let enabled = value
+            .parse::<bool>()
+            .map_err(|_| format!("invalid {} value '{}'", name, value))?;
+        engine.options.set(&name, enabled);
+        Ok(())
+    }
+
+    fn go(&mut self, params: GoParams) {
+        let board = self.game.board.clone_as_a();
+        let turn = self.game.turn;
+        let history = self.game.position_history.clone();
+        let limits = params.limits(turn);
+        let infinite = params.infinite;
+        let engine = Arc::clone(&self.engine);
+        let output = Arc::clone(&self.output);
+        let stop = Arc::clone(&self.stop);
+        stop.store(false, Ordering::Relaxed);
+        let (stopped, on_stop) = mpsc::channel::<()>();
+        self.stopped = Some(stopped);
+
+        self.search = Some(std::thread::spawn(move || {
+            let mut engine = engine.lock().unwrap();
+            let best_move = engine
+                .search(board.as_ref(), turn, &history, &limits, &stop, |info| {
+                    send(&output, &info_line(info));
+                })
+                .best_move();
+            drop(engine);
+            // An infinite search only reports its move once the GUI sends `stop`, even after
+            // finding a mate or reaching the maximum depth.
+            if infinite {
+                let _ = on_stop.recv();
+            }
+            match best_move {
+                Some(best_move) => send(&output, &format!("bestmove {}", best_move)),
+                None => send(&output, "bestmove 0000"),
+            }
+        }));
+    }
+
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.stopped = None;
+        self.wait();
+    }
+
+    fn wait(&mut self) {
+        if let Some(search) = self.search.take() {
+            let _ = search.join();
+        }
+    }
+}
+
+fn info_line(info: &SearchInfo) -> String {
+    let millis = info.time.as_millis().max(1) as u64;
+    let pv: Vec<String> = info.pv.iter().map(|m| m.to_string()).collect();
+    let score = match info.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", info.score),
+    };
+    format!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        info.depth,
+        score,
+        info.nodes,
+        info.nodes * 1000 / millis,
+        info.time.as_millis(),
+        pv.join(" ")
+    )
+}
+
+fn send<W: Write>(output: &Mutex<W>, line: &str) {
+    let mut output = output.lock().unwrap();
+    let _ = writeln!(output, "{}", line);
+    let _ = output.flush();
+}
+
+/// Runs a UCI session until `quit` or the end of `input`.
+pub fn run<W: Write + Send + 'static>(input: impl BufRead, output: W) {
+    let mut uci = Uci::new(output);
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !uci.handle(&line) {
+            return;
+        }
+    }
+    uci.stop_search();
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{Move, Position};
+
+    fn output(uci: &Uci<Vec<u8>>) -> String {
+        String::from_utf8(uci.output.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut uci = Uci::new(Vec::new());
+        assert!(uci.handle("uci"));
+        assert!(uci.handle("isready"));
+        let output = output(&uci);
+        assert!(output.starts_with("id name rchess"));
+        assert!(output.contains("uciok\nreadyok\n"));
+        assert!(!uci.handle("quit"));
+    }
+
+    #[test]
+    fn test_setoption_hash() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("uci");
+        assert!(output(&uci).contains("option name Hash type spin default 16"));
+
+        uci.handle("setoption name Hash value 1");
+        uci.handle("setoption name Hash value 0");
+        uci.handle("setoption name Contempt value 10");
+        let output = output(&uci);
+        assert!(output.contains("info string invalid Hash value '0'"));
+        assert!(output.contains("info string unknown option 'Contempt'"));
+    }
+
+    #[test]
+    fn test_setoption_search_switches() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("uci");
+        assert!(output(&uci).contains("option name NullMove type check default true"));
+
+        uci.handle("setoption name NullMove value false");
+        uci.handle("setoption name pvs value false");
+        uci.handle("setoption name Futility value maybe");
+        let options = uci.engine.lock().unwrap().options;
+        assert!(!options.null_move);
+        assert!(!options.pvs);
+        assert!(options.futility);
+        assert!(output(&uci).contains("info string invalid Futility value 'maybe'"));
+    }
+
+    #[test]
+    fn test_setoption_book() {
+        let start = crate::book::hash(Game::new().board.as_ref(), Color::White);
+        let mut entry = start.to_be_bytes().to_vec();
+        entry.extend([0x03, 0x1c, 0, 1, 0, 0, 0, 0]);
+        let path = std::env::temp_dir().join(format!("rchess-uci-{}.bin", std::process::id()));
+        std::fs::write(&path, entry).unwrap();
+
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("uci");
+        assert!(output(&uci).contains("option name BookDepth type spin default 20"));
+        uci.handle(&format!("setoption name BookFile value {}", path.display()));
+        uci.handle("setoption name BookSelection value Best");
+        uci.handle("setoption name BookDepth value 300");
+        uci.handle("setoption name BookFile value missing.bin");
+        std::fs::remove_file(&path).unwrap();
+        uci.handle("position startpos");
+        uci.handle("go depth 3");
+        uci.wait();
+
+        let output = output(&uci);
+        assert!(output.contains("info string invalid BookDepth value '300'"));
+        assert!(output.contains("info string invalid BookFile: "));
+        assert!(
+            output.ends_with("bestmove e2e4\n"),
+            "The book move is played"
+        );
+    }
+
+    #[test]
+    fn test_position_startpos_with_moves() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("position startpos moves e2e4 e7e5 g1f3");
+        assert_eq!(uci.game.turn, Color::Black);
+        assert_eq!(
+            uci.game.black.moves,
+            vec![Move::new(Position::new('e', 7), Position::new('e', 5))]
+        );
+    }
+
+    #[test]
+    fn test_position_fen_with_moves() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("position fen 4k3/1P6/8/8/8/8/8/4K3 w - - 0 1 moves b7b8n");
+        assert_eq!(uci.game.to_fen(), "1N2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+        uci.handle("position fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1 moves e1e3");
+        assert!(output(&uci).contains("info string"));
+        assert_eq!(uci.game.to_fen(), "1N2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn test_go_depth_reports_bestmove() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("position startpos moves e2e4");
+        uci.handle("go depth 1");
+        uci.wait();
+
+        let output = output(&uci);
+        assert!(output.contains("info depth 1 score cp"));
+        let best_move = output
+            .lines()
+            .find_map(|line| line.strip_prefix("bestmove "))
+            .expect("search should report a best move");
+        assert!(uci.game.parse_move(best_move).is_ok());
+    }
+
+    #[test]
+    fn test_go_infinite_until_stop() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("go infinite");
+        uci.handle("stop");
+        assert!(output(&uci).contains("bestmove "));
+    }
+
+    #[test]
+    fn test_go_infinite_waits_for_stop_after_mate() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        uci.handle("go infinite");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !output(&uci).contains("score mate 1") {
+            assert!(Instant::now() < deadline, "The mate is found");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        uci.handle("uci");
+        let before = output(&uci);
+        assert!(
+            before.contains("uciok"),
+            "Commands are read while searching"
+        );
+        assert!(!before.contains("bestmove"));
+
+        uci.handle("stop");
+        assert!(output(&uci).ends_with("bestmove a1a8\n"));
+    }
+
+    #[test]
+    fn test_go_without_legal_moves() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("position fen 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        uci.handle("go movetime 100");
+        uci.wait();
+        assert!(output(&uci).ends_with("bestmove 0000\n"));
+    }
+
+    #[test]
+    fn test_eval_prints_trace() {
+        let mut uci = Uci::new(Vec::new());
+        uci.handle("position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        uci.handle("eval");
+        let output = output(&uci);
+        assert!(output.contains("Passed pawns"));
+        assert!(output.ends_with(&format!(
+            "Final evaluation: {} (White side)\n",
+            eval::evaluate(uci.game.board.as_ref(), Color::White)
+        )));
+    }
+
+    #[test]
+    fn test_time_limits() {
+        let params = GoParams::parse(&["wtime", "60000", "btime", "900", "winc", "2000"]);
+        assert_eq!(
+            params.limits(Color::White).time,
+            Some(Duration::from_millis(3000))
+        );
+        assert_eq!(
+            params.limits(Color::Black).time,
+            Some(Duration::from_millis(30))
+        );
+
+        let params = GoParams::parse(&["movetime", "500", "depth", "4"]);
+        assert_eq!(
+            params.limits(Color::White),
+            SearchLimits {
+                depth: Some(4),
+                time: Some(Duration::from_millis(450)),
+            }
+        );
+
+        let params = GoParams::parse(&["btime", "40", "movestogo", "1"]);
+        assert_eq!(params.limits(Color::Black).time, Some(Duration::ZERO));
+        assert_eq!(
+            GoParams::parse(&["infinite"]).limits(Color::White).time,
+            None
+        );
+    }
+}
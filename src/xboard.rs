@@ -0,0 +1,532 @@
+use std::{
+    io::{BufRead, Write},
+    sync::atomic::AtomicBool,
+    time::Duration,
+};
+
+use crate::{
+    Game,
+    ai::{Engine, SearchInfo, SearchLimits, SearchOptions},
+    board::GameStatus,
+    book::{Book, MAX_BOOK_DEPTH, Selection},
+    pieces::Color,
+    tt::DEFAULT_HASH_MB,
+    uci::GoParams,
+};
+
+/// Thinking time used when neither `level`, `st` nor `time` have been sent.
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(5);
+
+/// A Chess Engine Communication Protocol session that writes its replies to `output`.
+pub struct XBoard<W: Write> {
+    game: Game,
+    output: W,
+    searcher: Engine,
+    force: bool,
+    engine: Color,
+    post: bool,
+    depth: Option<u8>,
+    move_time: Option<Duration>,
+    moves_per_session: u32,
+    increment: Duration,
+    time_left: Option<Duration>,
+}
+
+impl<W: Write> XBoard<W> {
+    pub fn new(output: W) -> Self {
+        XBoard {
+            game: Game::new(),
+            output,
+            searcher: Engine::new(DEFAULT_HASH_MB),
+            force: false,
+            engine: Color::Black,
+            post: false,
+            depth: None,
+            move_time: None,
+            moves_per_session: 0,
+            increment: Duration::ZERO,
+            time_left: None,
+        }
+    }
+
+    /// Handles one command line, returning false once the GUI has sent `quit`.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+
+        match command {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "otim" | "white" | "black" => {}
+            "protover" => {
+                for name in SearchOptions::NAMES {
+                    let enabled = self.searcher.options.get(name).unwrap_or_default();
+                    self.send(&format!(
+                        "feature option=\"{} -check {}\"",
+                        name, enabled as u8
+                    ));
+                }
+                let book = self.searcher.book_options;
+                self.send("feature option=\"BookFile -file \"");
+                self.send(&format!(
+                    "feature option=\"BookDepth -spin {} 0 {}\"",
+                    book.depth, MAX_BOOK_DEPTH
+                ));
+                let selections: Vec<String> = Selection::NAMES
+                    .iter()
+                    .map(|name| {
+                        if *name == book.selection.to_string() {
+                            format!("*{}", name)
+                        } else {
+                            name.to_string()
+                        }
+                    })
+                    .collect();
+                self.send(&format!(
+                    "feature option=\"BookSelection -combo {}\"",
+                    selections.join(" /// ")
+                ));
+                self.send(&format!(
+                    "feature myname=\"rchess {}\" ping=1 setboard=1 usermove=1 san=0 time=1 \
+                     memory=1 draw=0 sigint=0 sigterm=0 colors=0 analyze=0 done=1",
+                    env!("CARGO_PKG_VERSION")
+                ));
+            }
+            "new" => {
+                self.game = Game::new();
+                self.searcher.clear();
+                self.force = false;
+                self.engine = Color::Black;
+                self.depth = None;
+            }
+            "force" => self.force = true,
+            "go" => {
+                self.force = false;
+                self.engine = self.game.turn;
+                self.think();
+            }
+            "playother" => {
+                self.force = false;
+                self.engine = self.game.turn.opponent();
+            }
+            "usermove" => self.user_move(args),
+            "setboard" => match Game::from_fen(args) {
+                Ok(game) => self.game = game,
+                Err(err) => self.send(&format!("tellusererror Illegal position: {}", err)),
+            },
+            "undo" => {
+                self.game.undo();
+            }
+            "remove" => {
+                self.game.undo();
+                self.game.undo();
+            }
+            "level" => match parse_level(args) {
+                Some((moves_per_session, base, increment)) => {
+                    self.moves_per_session = moves_per_session;
+                    self.time_left = Some(base);
+                    self.increment = increment;
+                    self.move_time = None;
+                }
+                None => self.error(line, "expected level MPS BASE INC"),
+            },
+            "st" => match args.parse::<f64>() {
+                Ok(seconds) if seconds >= 0.0 => {
+                    self.move_time = Some(Duration::from_secs_f64(seconds))
+                }
+                _ => self.error(line, "expected st SECONDS"),
+            },
+            "sd" => match args.parse::<u8>() {
+                Ok(depth) => self.depth = Some(depth),
+                Err(_) => self.error(line, "expected sd DEPTH"),
+            },
+            "time" => match args.parse::<u64>() {
+                Ok(centiseconds) => self.time_left = Some(Duration::from_millis(centiseconds * 10)),
+                Err(_) => self.error(line, "expected time CENTISECONDS"),
+            },
+            "memory" => match args.parse::<usize>() {
+                Ok(megabytes) if megabytes > 0 => self.searcher.set_hash_size(megabytes),
+                _ => self.error(line, "expected memory MEGABYTES"),
+            },
+            "option" => {
+                if let Err(reason) = self.set_option(args) {
+                    self.error(line, &reason);
+                }
+            }
+            "ping" => self.send(&format!("pong {}", args)),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "result" => self.force = true,
+            // The search runs to its limits before the next command is read.
+            "?" => self.error(line, "not implemented"),
+            "quit" => return false,
+            _ => {
+                // Without the usermove feature moves arrive as bare commands.
+                if self.game.parse_move(line).is_ok() {
+                    self.user_move(line);
+                } else {
+                    self.error(line, "unknown command");
+                }
+            }
+        }
+
+        true
+    }
+
+    fn user_move(&mut self, text: &str) {
+        let m = match self.game.parse_move(text) {
+            Ok(m) => m,
+            Err(_) => {
+                self.send(&format!("Illegal move: {}", text));
+                return;
+            }
+        };
+        if self.game.make_move(&m).is_err() {
+            self.send(&format!("Illegal move: {}", text));
+            return;
+        }
+
+        if !self.report_result() && !self.force && self.game.turn == self.engine {
+            self.think();
+        }
+    }
+
+    /// Handles `option NAME=VALUE` for the options announced in `protover`.
+    fn set_option(&mut self, args: &str) -> Result<(), String> {
+        let (name, value) = args.split_once('=').unwrap_or((args, ""));
+        match name {
+            "BookFile" => {
+                let book = match value {
+                    "" => None,
+                    path => Some(Book::open(path).map_err(|err| format!("invalid book: {}", err))?),
+                };
+                self.searcher.set_book(book);
+            }
+            "BookDepth" => {
+                self.searcher.book_options.depth = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|depth| *depth <= MAX_BOOK_DEPTH)
+                    .ok_or("expected option BookDepth=DEPTH")?;
+            }
+            "BookSelection" => {
+                self.searcher.book_options.selection =
+                    value.parse().map_err(|err| format!("{}", err))?;
+            }
+            _ => {
+                let switched =
+                    matches!(value, "0" | "1") && self.searcher.options.set(name, value == "1");
+                if !switched {
+                    return Err("expected option NAME=0|1".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn think(&mut self) {
+        if self.report_result() {
+            return;
+        }
+
+        let limits = self.limits();
+        let stop = AtomicBool::new(false);
+        let post = self.post;
+        let output = &mut self.output;
+        let best_move = self
+            .searcher
+            .search(
+                self.game.board.as_ref(),
+                self.game.turn,
+                &self.game.position_history,
+                &limits,
+                &stop,
+                |info| {
+                    if post {
+                        let _ = writeln!(output, "{}", thinking_line(info));
+                        let _ = output.flush();
+                    }
+                },
+            )
+            .best_move();
+
+        let Some(best_move) = best_move else {
+            return;
+        };
+        self.game
+            .make_move(&best_move)
+            .expect("the search only returns legal moves");
+        self.send(&format!("move {}", best_move));
+        self.report_result();
+    }
+
+    fn limits(&self) -> SearchLimits {
+        if let Some(move_time) = self.move_time {
+            return SearchLimits {
+                depth: self.depth,
+                time: Some(move_time),
+            };
+        }
+
+        let Some(time_left) = self.time_left else {
+            return SearchLimits {
+                depth: self.depth,
+                time: self.depth.is_none().then_some(DEFAULT_MOVE_TIME),
+            };
+        };
+        let movestogo = (self.moves_per_session > 0).then(|| {
+            let played = self.game.player(self.engine).moves.len() as u32;
+            self.moves_per_session - played % self.moves_per_session
+        });
+        let params = GoParams {
+            depth: self.depth,
+            wtime: Some(time_left),
+            winc: Some(self.increment),
+            movestogo,
+            ..GoParams::default()
+        };
+
+        params.limits(Color::White)
+    }
+
+    /// Announces the result once the game has ended, returning whether it has.
+    fn report_result(&mut self) -> bool {
+        let turn = self.game.turn;
+        let result = match self.game.board.status(&turn) {
+            GameStatus::Checkmate => match turn {
+                Color::White => "0-1 {Black mates}".to_string(),
+                Color::Black => "1-0 {White mates}".to_string(),
+            },
+            GameStatus::Stalemate => "1/2-1/2 {Stalemate}".to_string(),
+            GameStatus::Check | GameStatus::Ongoing => match self.game.draw_reason() {
+                Some(reason) => format!("1/2-1/2 {{Draw by {}}}", reason),
+                None => return false,
+            },
+        };
+
+        self.send(&result);
+        true
+    }
+
+    fn error(&mut self, line: &str, reason: &str) {
+        self.send(&format!("Error ({}): {}", reason, line));
+    }
+
+    fn send(&mut self, line: &str) {
+        let _ = writeln!(self.output, "{}", line);
+        let _ = self.output.flush();
+    }
+}
+
+/// Reads `level MPS BASE INC`, where BASE is minutes or `minutes:seconds` and INC is seconds.
+fn parse_level(args: &str) -> Option<(u32, Duration, Duration)> {
+    let mut args = args.split_whitespace();
+    let moves_per_session = args.next()?.parse::<u32>().ok()?;
+    let base = args.next()?;
+    let base = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?
+        }
+        None => base.parse::<u64>().ok()? * 60,
+    };
+    let increment = args.next()?.parse::<f64>().ok().filter(|inc| *inc >= 0.0)?;
+    if args.next().is_some() {
+        return None;
+    }
+
+    Some((
+        moves_per_session,
+        Duration::from_secs(base),
+        Duration::from_secs_f64(increment),
+    ))
+}
+
+/// CECP thinking output: ply, score in centipawns, time in centiseconds, nodes and the PV.
+fn thinking_line(info: &SearchInfo) -> String {
+    let pv: Vec<String> = info.pv.iter().map(|m| m.to_string()).collect();
+    format!(
+        "{} {} {} {} {}",
+        info.depth,
+        info.score,
+        info.time.as_millis() / 10,
+        info.nodes,
+        pv.join(" ")
+    )
+}
+
+/// Runs a CECP session until `quit` or the end of `input`.
+pub fn run<W: Write>(input: impl BufRead, output: W) {
+    let mut xboard = XBoard::new(output);
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !xboard.handle(&line) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn output(xboard: &mut XBoard<Vec<u8>>) -> String {
+        let output = String::from_utf8(xboard.output.clone()).unwrap();
+        xboard.output.clear();
+        output
+    }
+
+    #[test]
+    fn test_feature_negotiation() {
+        let mut xboard = XBoard::new(Vec::new());
+        xboard.handle("xboard");
+        xboard.handle("protover 2");
+        let features = output(&mut xboard);
+        assert!(features.starts_with("feature option=\"NullMove -check 1\"\n"));
+        assert!(features.contains("feature myname=\"rchess"));
+        assert!(features.contains("usermove=1"));
+        assert!(features.trim_end().ends_with("done=1"));
+
+        xboard.handle("accepted usermove");
+        xboard.handle("memory 1");
+        xboard.handle("ping 7");
+        assert_eq!(output(&mut xboard), "pong 7\n");
+        xboard.handle("option LateMoveReductions=0");
+        assert!(!xboard.searcher.options.late_move_reductions);
+        assert!(features.contains("feature option=\"BookSelection -combo *Random /// Best\""));
+        xboard.handle("option BookSelection=Best");
+        xboard.handle("option BookDepth=8");
+        assert_eq!(xboard.searcher.book_options.selection, Selection::Best);
+        assert_eq!(xboard.searcher.book_options.depth, 8);
+        xboard.handle("option BookFile=missing.bin");
+        assert!(output(&mut xboard).starts_with("Error (invalid book: "));
+        xboard.handle("memory lots");
+        assert_eq!(
+            output(&mut xboard),
+            "Error (expected memory MEGABYTES): memory lots\n"
+        );
+        assert!(!xboard.handle("quit"));
+    }
+
+    #[test]
+    fn test_engine_replies_to_user_move() {
+        let mut xboard = XBoard::new(Vec::new());
+        xboard.handle("new");
+        xboard.handle("st 1");
+        xboard.handle("usermove e2e4");
+        let reply = output(&mut xboard);
+        let engine_move = reply
+            .strip_prefix("move ")
+            .expect("engine should answer with a move")
+            .trim();
+        assert_eq!(xboard.game.turn, Color::White);
+        assert_eq!(xboard.game.black.moves.len(), 1);
+        assert_eq!(xboard.game.black.moves[0].to_string(), engine_move);
+    }
+
+    #[test]
+    fn test_force_mode_and_go() {
+        let mut xboard = XBoard::new(Vec::new());
+        xboard.handle("new");
+        xboard.handle("sd 1");
+        xboard.handle("force");
+        xboard.handle("usermove e2e4");
+        xboard.handle("usermove e7e5");
+        assert_eq!(output(&mut xboard), "");
+
+        xboard.handle("go");
+        assert!(output(&mut xboard).starts_with("move "));
+        assert_eq!(xboard.engine, Color::White);
+        assert_eq!(xboard.game.turn, Color::Black);
+    }
+
+    #[test]
+    fn test_post_thinking_output() {
+        let mut xboard = XBoard::new(Vec::new());
+        xboard.handle("new");
+        xboard.handle("sd 2");
+        xboard.handle("post");
+        xboard.handle("force");
+        xboard.handle("go");
+        let thinking = output(&mut xboard);
+        let lines: Vec<&str> = thinking.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", thinking);
+        assert!(lines[0].starts_with("1 "));
+        assert!(lines[1].starts_with("2 "));
+        assert!(lines[2].starts_with("move "));
+
+        xboard.handle("nopost");
+        xboard.handle("go");
+        assert!(output(&mut xboard).starts_with("move "));
+    }
+
+    #[test]
+    fn test_setboard_undo_and_remove() {
+        let mut xboard = XBoard::new(Vec::new());
+        xboard.handle("force");
+        xboard.handle("setboard 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        xboard.handle("usermove e2e4");
+        xboard.handle("usermove e8d7");
+        xboard.handle("undo");
+        assert_eq!(xboard.game.to_fen(), "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1");
+        xboard.handle("usermove e8d7");
+        xboard.handle("remove");
+        assert_eq!(xboard.game.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+
+        xboard.handle("setboard not a fen");
+        assert!(output(&mut xboard).starts_with("tellusererror Illegal position"));
+    }
+
+    #[test]
+    fn test_illegal_and_unknown_commands() {
+        let mut xboard = XBoard::new(Vec::new());
+        xboard.handle("usermove e2e5");
+        assert_eq!(output(&mut xboard), "Illegal move: e2e5\n");
+        xboard.handle("foo");
+        assert_eq!(output(&mut xboard), "Error (unknown command): foo\n");
+        xboard.handle("?");
+        assert_eq!(output(&mut xboard), "Error (not implemented): ?\n");
+    }
+
+    #[test]
+    fn test_result_reporting() {
+        let mut xboard = XBoard::new(Vec::new());
+        xboard.handle("force");
+        xboard.handle("setboard rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2");
+        xboard.handle("usermove d8h4");
+        assert_eq!(output(&mut xboard), "0-1 {Black mates}\n");
+
+        xboard.handle("setboard 7k/8/6K1/8/8/8/8/5Q2 w - - 0 1");
+        xboard.handle("usermove f1f7");
+        assert_eq!(output(&mut xboard), "1/2-1/2 {Stalemate}\n");
+    }
+
+    #[test]
+    fn test_time_controls() {
+        assert_eq!(
+            parse_level("40 5 0"),
+            Some((40, Duration::from_secs(300), Duration::ZERO))
+        );
+        assert_eq!(
+            parse_level("0 0:30 2.5"),
+            Some((0, Duration::from_secs(30), Duration::from_millis(2500)))
+        );
+        assert_eq!(parse_level("40 5"), None);
+
+        let mut xboard = XBoard::new(Vec::new());
+        assert_eq!(xboard.limits().time, Some(DEFAULT_MOVE_TIME));
+        xboard.handle("level 40 5 0");
+        xboard.handle("time 12000");
+        assert_eq!(xboard.limits().time, Some(Duration::from_secs(3)));
+        xboard.handle("st 2");
+        xboard.handle("sd 3");
+        assert_eq!(
+            xboard.limits(),
+            SearchLimits {
+                depth: Some(3),
+                time: Some(Duration::from_secs(2)),
+            }
+        );
+    }
+}
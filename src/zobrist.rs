@@ -0,0 +1,205 @@
+use crate::{
+    Position,
+    board::{BOARD_SQUARES, BoardTrait, castling_rights},
+    pieces::{Color, Piece, PieceType},
+};
+
+const PIECE_KEYS: usize = 2 * 6 * BOARD_SQUARES as usize;
+const CASTLING_KEYS: usize = 4;
+const EN_PASSANT_KEYS: usize = 8;
+const KEY_COUNT: usize = PIECE_KEYS + CASTLING_KEYS + EN_PASSANT_KEYS + 1;
+const SEED: u64 = 0x5EED_C0DE_CAFE_F00D;
+
+/// Random keys, generated at compile time so a position hashes the same in every build.
+static KEYS: [u64; KEY_COUNT] = generate_keys();
+
+/// Fills the key table with the splitmix64 generator.
+const fn generate_keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state = SEED;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Returns the key of `piece` standing on its square.
+pub fn piece(piece: &PieceType) -> u64 {
+    let kind = match piece {
+        PieceType::Pawn(_, _, _) => 0,
+        PieceType::Knight(_, _) => 1,
+        PieceType::Bishop(_, _) => 2,
+        PieceType::Rook(_, _, _) => 3,
+        PieceType::Queen(_, _) => 4,
+        PieceType::King(_, _, _) => 5,
+    };
+    let color = match piece.color() {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    KEYS[(color * 6 + kind) * BOARD_SQUARES as usize + piece.position().to_index() as usize]
+}
+
+/// Returns the combined key of castling rights written as `KQkq`.
+pub fn castling(rights: &str) -> u64 {
+    rights
+        .chars()
+        .filter_map(|right| "KQkq".find(right))
+        .fold(0, |key, index| key ^ KEYS[PIECE_KEYS + index])
+}
+
+/// Returns the key of an en passant target square, which only depends on its file.
+pub fn en_passant(position: &Position) -> u64 {
+    KEYS[PIECE_KEYS + CASTLING_KEYS + (position.x as usize - 'a' as usize)]
+}
+
+/// Returns the key of the en passant square of `board`, or nothing when no pawn can capture
+/// on it, so positions that only differ by a double pawn push still repeat.
+pub fn en_passant_target(board: &dyn BoardTrait) -> u64 {
+    let Some(target) = board.en_passant() else {
+        return 0;
+    };
+    let (rank, color) = match target.y {
+        3 => (1, Color::Black),
+        _ => (-1, Color::White),
+    };
+    let capturable = [-1, 1].into_iter().any(|df| {
+        target
+            .offset(df, rank)
+            .and_then(|position| board.get_piece(position))
+            .is_some_and(|p| matches!(p, PieceType::Pawn(_, _, _)) && *p.color() == color)
+    });
+    if capturable { en_passant(&target) } else { 0 }
+}
+
+/// Returns the key of the side to move. White to move adds nothing.
+pub fn side(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        Color::Black => KEYS[KEY_COUNT - 1],
+    }
+}
+
+/// Hashes pieces, castling rights and the capturable en passant square of `board` from scratch.
+/// Boards keep this key up to date incrementally, see `BoardTrait::zobrist_key`.
+pub fn hash(board: &dyn BoardTrait) -> u64 {
+    let key = board
+        .get_all_white_pieces()
+        .into_iter()
+        .chain(board.get_all_black_pieces())
+        .fold(0, |key, p| key ^ piece(p));
+    key ^ castling(&castling_rights(board)) ^ en_passant_target(board)
+}
+
+/// Hashes the pawns of `board` alone, the key of its pawn structure.
+pub fn pawn_hash(board: &dyn BoardTrait) -> u64 {
+    board
+        .get_all_white_pieces()
+        .into_iter()
+        .chain(board.get_all_black_pieces())
+        .filter(|p| matches!(p, PieceType::Pawn(_, _, _)))
+        .fold(0, |key, p| key ^ piece(p))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{Move, board, fen::Fen};
+
+    #[test]
+    fn test_keys_are_unique() {
+        let keys: HashSet<u64> = KEYS.iter().copied().collect();
+        assert_eq!(keys.len(), KEY_COUNT);
+        assert!(!keys.contains(&0));
+    }
+
+    #[test]
+    fn test_incremental_key_matches_full_hash() {
+        let fen: Fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        assert_eq!(fen.board.zobrist_key(), hash(fen.board.as_ref()));
+        assert_eq!(fen.board.pawn_key(), pawn_hash(fen.board.as_ref()));
+
+        for m in fen.board.legal_moves(&fen.turn) {
+            let mut next = fen.board.clone_as_a();
+            next.make_move(&m).unwrap();
+            assert_eq!(next.zobrist_key(), hash(next.as_ref()), "after {}", m);
+            assert_eq!(next.pawn_key(), pawn_hash(next.as_ref()), "after {}", m);
+
+            for reply in next.legal_moves(&fen.turn.opponent()) {
+                let mut last = next.clone_as_a();
+                last.make_move(&reply).unwrap();
+                assert_eq!(
+                    last.zobrist_key(),
+                    hash(last.as_ref()),
+                    "after {} {}",
+                    m,
+                    reply
+                );
+                assert_eq!(
+                    last.pawn_key(),
+                    pawn_hash(last.as_ref()),
+                    "after {} {}",
+                    m,
+                    reply
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_transpositions_share_a_key() {
+        let play = |moves: &[(&str, &str)]| {
+            let mut board = board::new_board();
+            for (from, to) in moves {
+                board
+                    .make_move(&Move::new(from.parse().unwrap(), to.parse().unwrap()))
+                    .unwrap();
+            }
+            board.zobrist_key()
+        };
+
+        let knights = play(&[("g1", "f3"), ("g8", "f6"), ("b1", "c3"), ("b8", "c6")]);
+        let swapped = play(&[("b1", "c3"), ("b8", "c6"), ("g1", "f3"), ("g8", "f6")]);
+        assert_eq!(knights, swapped);
+
+        let back_and_forth = play(&[("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")]);
+        assert_eq!(back_and_forth, board::new_board().zobrist_key());
+
+        let rook_shuffle = play(&[
+            ("g1", "f3"),
+            ("g8", "f6"),
+            ("h1", "g1"),
+            ("f6", "g8"),
+            ("g1", "h1"),
+            ("g8", "f6"),
+            ("f3", "g1"),
+            ("f6", "g8"),
+        ]);
+        assert_ne!(
+            rook_shuffle,
+            board::new_board().zobrist_key(),
+            "White lost the right to castle king side"
+        );
+    }
+
+    #[test]
+    fn test_en_passant_and_side_change_the_key() {
+        let with: Fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 3".parse().unwrap();
+        let without: Fen = "4k3/8/8/3pP3/8/8/8/4K3 w - - 0 3".parse().unwrap();
+        assert_ne!(with.board.zobrist_key(), without.board.zobrist_key());
+        let uncapturable: Fen = "4k3/8/8/3p4/8/8/8/4K3 w - d6 0 3".parse().unwrap();
+        let plain: Fen = "4k3/8/8/3p4/8/8/8/4K3 w - - 0 3".parse().unwrap();
+        assert_eq!(uncapturable.board.zobrist_key(), plain.board.zobrist_key());
+        assert_ne!(side(Color::White), side(Color::Black));
+    }
+}